@@ -1,5 +1,5 @@
 import pygame
-from dungeon_core import Dungeon, Entity  # Rust module
+from dungeon_core import DoorKind, Dungeon, EnemyKind, Entity, GameState, Item  # Rust module
 
 CELL_SIZE = 40
 
@@ -14,6 +14,26 @@ MINIMAP_PADDING = 30
 MINIMAP_ROOM_MARGIN = 4
 ICON_PATH = "icons/"
 
+# Only the bat has its own sprite so far; the other kinds reuse it with a tint
+ENEMY_TINTS = {
+    str(EnemyKind.Slime): (120, 255, 120, 255),
+    str(EnemyKind.Slimeling): (170, 255, 170, 255),
+    str(EnemyKind.Skeleton): (230, 230, 230, 255),
+    str(EnemyKind.Ghost): (170, 170, 255, 150),
+    str(EnemyKind.Tank): (255, 120, 120, 255),
+    str(EnemyKind.Boss): (255, 190, 40, 255),
+}
+
+# Doors between rooms; open doors aren't drawn
+DOOR_COLORS = {
+    str(DoorKind.Locked): (255, 215, 0),
+    str(DoorKind.OneWay): (80, 160, 255),
+    str(DoorKind.Sealed): (200, 0, 0),
+}
+
+# Number keys pick an offer in a merchant's stock list
+SHOP_KEYS = [pygame.K_1, pygame.K_2, pygame.K_3, pygame.K_4, pygame.K_5]
+
 DIRECTION_KEYS = {
     pygame.K_UP: "up",
     pygame.K_DOWN: "down",
@@ -84,6 +104,15 @@ class DungeonGUI:
             text_surface = font.render(value, True, (255, 255, 255))
             self.screen.blit(text_surface, (x, y + (icon.get_height() - font.get_height()) // 2))
             x += text_surface.get_width() + 20  # Space between stats
+
+        # Keys collected towards the exit door, and potions saved for later (H to drink)
+        potions = self.dungeon.player.inventory.count(Item.Potion)
+        keys_text = f"Keys {self.dungeon.player.keys}/{self.dungeon.keys_required}   Potions {potions}"
+        if self.dungeon.boss_phase is not None:
+            # Boss rooms stay sealed until the boss is dead
+            keys_text += "   Boss " + str(self.dungeon.boss_phase).split(".")[-1] + " - room sealed"
+        text_surface = font.render(keys_text, True, (255, 215, 0))
+        self.screen.blit(text_surface, (x, y))
     
     def draw_enemy_info(self, enemy, mouse_pos):
         font = pygame.font.SysFont("Arial", 18)
@@ -160,6 +189,37 @@ class DungeonGUI:
                 y = self.stats_height + PADDING + chest.row * CELL_SIZE
                 self.screen.blit(self.chest_image, (x, y))
     
+    def draw_exit(self):
+        # The exit door only lives in one room
+        if (self.dungeon.current_room_row, self.dungeon.current_room_col) != self.dungeon.exit_room:
+            return
+        exit_x, exit_y = self.dungeon.exit_cell
+        x = PADDING + exit_x * CELL_SIZE
+        y = self.stats_height + PADDING + exit_y * CELL_SIZE
+        color = (0, 200, 0) if self.dungeon.exit_unlocked else (200, 0, 0)
+        pygame.draw.rect(self.screen, color, pygame.Rect(x + 4, y + 4, CELL_SIZE - 8, CELL_SIZE - 8), 3)
+
+    def draw_doors(self):
+        # Doors sit across the room's exits in the middle of each edge
+        here = (self.dungeon.current_room_row, self.dungeon.current_room_col)
+        for door in self.dungeon.doors_here():
+            color = DOOR_COLORS.get(str(door.kind))
+            if color is None:
+                continue
+            other = door.to_room if door.from_room == here else door.from_room
+            dr, dc = other[0] - here[0], other[1] - here[1]
+            left = PADDING
+            top = self.stats_height + PADDING
+            mid_x = left + (self.maze_width // 2) * CELL_SIZE
+            mid_y = top + (self.maze_height // 2) * CELL_SIZE
+            if dr:
+                y = top if dr < 0 else top + self.maze_height * CELL_SIZE
+                start, end = (mid_x, y), (mid_x + CELL_SIZE, y)
+            else:
+                x = left if dc < 0 else left + self.maze_width * CELL_SIZE
+                start, end = (x, mid_y), (x, mid_y + CELL_SIZE)
+            pygame.draw.line(self.screen, color, start, end, WALL_THICKNESS * 2)
+
     def draw_player(self, player):
         # Load hero image once and cache it
         if not hasattr(self, "hero_image"):
@@ -170,17 +230,47 @@ class DungeonGUI:
         y = self.stats_height + PADDING + player.y * CELL_SIZE  # Offset by stats bar
         self.screen.blit(self.hero_image, (x, y))
     
-    def draw_enemies(self, enemies):
-        # Load enemy image once and cache it
-        if not hasattr(self, "enemy_image"):
+    def enemy_sprite(self, kind):
+        # Load enemy image once and cache a tinted copy per kind
+        if not hasattr(self, "enemy_images"):
             path = ICON_PATH + "enemy_bat.png"
             img = pygame.image.load(path).convert_alpha()
             self.enemy_image = pygame.transform.smoothscale(img, (CELL_SIZE, CELL_SIZE))
+            self.enemy_images = {}
+        key = str(kind)
+        if key not in self.enemy_images:
+            img = self.enemy_image.copy()
+            tint = ENEMY_TINTS.get(key)
+            if tint:
+                img.fill(tint, special_flags=pygame.BLEND_RGBA_MULT)
+            self.enemy_images[key] = img
+        return self.enemy_images[key]
+
+    def draw_enemies(self, enemies):
         for enemy in enemies:
             # Only draw enemies in the current room
             x = PADDING + enemy.x * CELL_SIZE
             y = self.stats_height + PADDING + enemy.y * CELL_SIZE
-            self.screen.blit(self.enemy_image, (x, y))
+            self.screen.blit(self.enemy_sprite(enemy.kind), (x, y))
+
+    def draw_fog(self):
+        # Unseen cells stay black; seen but out of sight cells are dimmed
+        if not hasattr(self, "fog_surface"):
+            self.fog_surface = pygame.Surface((CELL_SIZE, CELL_SIZE), pygame.SRCALPHA)
+            self.fog_surface.fill((0, 0, 0, 160))
+        explored = self.dungeon.explored_cells(self.dungeon.current_room_row, self.dungeon.current_room_col)
+        visible = self.dungeon.visible_cells()
+        for row in range(self.maze_height):
+            for col in range(self.maze_width):
+                if visible[row][col]:
+                    continue
+                x = PADDING + col * CELL_SIZE
+                y = self.stats_height + PADDING + row * CELL_SIZE
+                if explored[row][col]:
+                    self.screen.blit(self.fog_surface, (x, y))
+                else:
+                    # Grow by one pixel so walls on the cell edge are covered too
+                    pygame.draw.rect(self.screen, (0, 0, 0), pygame.Rect(x - 1, y - 1, CELL_SIZE + 2, CELL_SIZE + 2))
 
     def draw_minimap(self):
         # Top-left corner of minimap
@@ -190,18 +280,62 @@ class DungeonGUI:
         # Get current room position
         room_row = self.dungeon.current_room_row
         room_col = self.dungeon.current_room_col
+        exploration = self.dungeon.exploration()
 
         for row in range(self.dungeon_rows):
             for col in range(self.dungeon_cols):
                 rx = minimap_x + col * (MINIMAP_ROOM_SIZE + MINIMAP_ROOM_MARGIN)
                 ry = minimap_y + row * (MINIMAP_ROOM_SIZE + MINIMAP_ROOM_MARGIN)
                 rect = pygame.Rect(rx, ry, MINIMAP_ROOM_SIZE, MINIMAP_ROOM_SIZE)
-                color = (100, 100, 100)
+                if not self.dungeon.visited_rooms[row][col]:
+                    # Rooms never entered are only outlined
+                    pygame.draw.rect(self.screen, (60, 60, 60), rect, 1)
+                    continue
+                # Brighter the more of the room has been seen
+                shade = 60 + int(140 * exploration[row][col])
+                color = (shade, shade, shade)
                 if row == room_row and col == room_col:
                     color = (0, 255, 0)  # Highlight current room
                 pygame.draw.rect(self.screen, color, rect)
                 pygame.draw.rect(self.screen, (255, 255, 255), rect, 2)  # Border
 
+        # Doors show up once either side has been visited
+        step = MINIMAP_ROOM_SIZE + MINIMAP_ROOM_MARGIN
+        for door in self.dungeon.doors:
+            color = DOOR_COLORS.get(str(door.kind))
+            (r1, c1), (r2, c2) = door.from_room, door.to_room
+            if color is None or not (self.dungeon.visited_rooms[r1][c1] or self.dungeon.visited_rooms[r2][c2]):
+                continue
+            cx = minimap_x + (c1 + c2) * step // 2 + MINIMAP_ROOM_SIZE // 2
+            cy = minimap_y + (r1 + r2) * step // 2 + MINIMAP_ROOM_SIZE // 2
+            pygame.draw.rect(self.screen, color, pygame.Rect(cx - 3, cy - 3, 6, 6))
+
+    def draw_shop(self):
+        # Stock list under the minimap while standing in a merchant room
+        shop = self.dungeon.shop()
+        if shop is None:
+            return
+        font = pygame.font.SysFont("Arial", 18)
+        x = PADDING * 2 + self.maze_width * CELL_SIZE + MINIMAP_PADDING
+        y = self.stats_height + MINIMAP_PADDING + self.dungeon_rows * (MINIMAP_ROOM_SIZE + MINIMAP_ROOM_MARGIN) + 10
+        self.screen.blit(font.render("Merchant", True, (255, 215, 0)), (x, y))
+        for i, (item, count) in enumerate(shop.stock[:len(SHOP_KEYS)]):
+            y += font.get_height() + 4
+            name = str(item).split(".")[-1]
+            line = f"{i + 1}: {name} x{count}  {shop.price(item)}g"
+            color = (255, 255, 255) if self.dungeon.player.gold >= shop.price(item) else (120, 120, 120)
+            self.screen.blit(font.render(line, True, color), (x, y))
+
+    def buy_offer(self, index):
+        shop = self.dungeon.shop()
+        if shop is None or index >= len(shop.stock):
+            return
+        item, _ = shop.stock[index]
+        try:
+            self.dungeon.buy(item)
+        except ValueError:
+            pass  # Not enough gold; the greyed out price already says so
+
     def move_player(self, direction):
         self.dungeon.move_player(direction)
         self.player.x = self.dungeon.player.x
@@ -220,12 +354,24 @@ class DungeonGUI:
                 if event.type == pygame.QUIT:
                     running = False
                 elif event.type == pygame.KEYDOWN:
+                    if event.key == pygame.K_h and self.dungeon.player.inventory.count(Item.Potion) > 0:
+                        self.dungeon.use_item(Item.Potion)
+                    if event.key == pygame.K_p:
+                        if self.dungeon.state == GameState.Paused:
+                            self.dungeon.resume()
+                        else:
+                            self.dungeon.pause()
+                    if event.key in SHOP_KEYS:
+                        self.buy_offer(SHOP_KEYS.index(event.key))
                     direction = DIRECTION_KEYS.get(event.key)
                     if direction:
                         secsess = self.move_player(direction)
             self.refresh()
-            # After handling events, check if mouse is over any enemy
+            # After handling events, check if mouse is over any enemy the player can see
+            visible = self.dungeon.visible_cells()
             for enemy in self.dungeon.enemies:
+                if not visible[enemy.y][enemy.x]:
+                    continue
                 enemy_rect = pygame.Rect(
                     PADDING + enemy.x * CELL_SIZE,
                     self.stats_height + PADDING + enemy.y * CELL_SIZE,
@@ -247,31 +393,26 @@ class DungeonGUI:
         maze = self.dungeon.current_maze()
         self.draw_player_stats()
         self.draw_maze(maze)
+        self.draw_exit()
+        self.draw_doors()
         self.draw_player(self.player)
         self.draw_minimap()
+        self.draw_shop()
         self.draw_chests()
-        self.draw_enemies(self.dungeon.enemies)
+        # Enemies out of sight would still show through the dimmed fog
+        visible = self.dungeon.visible_cells()
+        self.draw_enemies([e for e in self.dungeon.enemies if visible[e.y][e.x]])
+        self.draw_fog()
+        if self.dungeon.state == GameState.Paused:
+            font = pygame.font.SysFont("Arial", 48)
+            text = font.render("Paused", True, (255, 255, 0))
+            self.screen.blit(text, text.get_rect(center=(self.screen.get_width() // 2, self.screen.get_height() // 2)))
         pygame.display.flip()
     
     def win_check(self):
         font = pygame.font.SysFont("Arial", 48)
-        # Check if player is at an exit position in a corner room
-        # Exits are always in the middle cell of each corner room
-        exits = [
-            (0, 0, int(self.maze_height // 2), 0),  # top-left room, middle of left edge
-            (0, self.dungeon_cols - 1, int(self.maze_height // 2), self.maze_width - 1),  # top-right room, middle of right edge
-            (self.dungeon_rows - 1, 0, int(self.maze_height // 2), 0),  # bottom-left room, middle of left edge
-            (self.dungeon_rows - 1, self.dungeon_cols - 1, int(self.maze_height // 2), self.maze_width - 1),  # bottom-right room, middle of right edge
-        ]
-        player_room_row = self.dungeon.current_room_row
-        player_room_col = self.dungeon.current_room_col
-        player_y = self.dungeon.player.y
-        player_x = self.dungeon.player.x
-        at_exit = any(
-            (player_room_row == er and player_room_col == ec and player_y == ey and player_x == ex)
-            for er, ec, ey, ex in exits
-        )
-        if not at_exit:
+        # The exit door in the starting room opens once every corner key is held
+        if self.dungeon.state != GameState.Won:
             return
         text = font.render("You Win!", True, (0, 255, 0))
         rect = text.get_rect(center=(self.screen.get_width() // 2, self.screen.get_height() // 2))
@@ -306,7 +447,7 @@ class DungeonGUI:
             pygame.time.wait(10)
 
     def eng_check(self):
-        if self.dungeon.player.health <= 0:
+        if self.dungeon.state == GameState.Lost:
             font = pygame.font.SysFont("Arial", 48)
             text = font.render("Game Over!", True, (255, 0, 0))
             rect = text.get_rect(center=(self.screen.get_width() // 2, self.screen.get_height() // 2))
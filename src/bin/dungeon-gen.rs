@@ -0,0 +1,291 @@
+// --- dungeon-gen: build dungeons from the command line ---
+//
+// Generates one dungeon, or a batch of consecutive seeds, without a window and prints
+// it in one of three formats:
+//
+//   ascii   every room in the `Maze::display` format, chests included
+//   json    one JSON document per dungeon (JSON Lines for batches); each room's
+//           "maze" uses the room schema from `maze/json.rs`
+//   stats   one tab-separated row of summary numbers per dungeon, with a header
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::process::ExitCode;
+use dungeon_core::dungeon::Dungeon;
+use dungeon_core::dungeon::door::DoorKind;
+use dungeon_core::entity::Entity;
+use dungeon_core::maze::Maze;
+use dungeon_core::maze::chest::ChestContent;
+use dungeon_core::maze::generator::Algorithm;
+use dungeon_core::maze::json;
+use dungeon_core::rng;
+use dungeon_core::{Error, Result};
+use serde::Serialize;
+
+const USAGE: &str = "\
+Usage: dungeon-gen [options]
+
+Options:
+  --rows N             rooms per column (default 5)
+  --cols N             rooms per row (default 5)
+  --room-size WxH      cells per room, or a single number for square rooms (default 11x11)
+  --seed N             dungeon seed (default: random)
+  --count N            generate N dungeons from consecutive seeds (default 1)
+  --algorithm NAMES    comma-separated carving algorithms, or \"all\" (default recursive_backtracker)
+  --braid F            fraction of dead ends opened into loops, 0 to 1 (default 0)
+  --valid              retry following seeds until the dungeon passes validation
+  --format FORMAT      ascii, json or stats (default ascii)
+  --output PATH        write to PATH instead of stdout
+  -h, --help           show this help";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    Json,
+    Stats,
+}
+
+struct Options {
+    rows: usize,
+    cols: usize,
+    width: usize,
+    height: usize,
+    seed: u64,
+    count: u64,
+    algorithms: Vec<Algorithm>,
+    braid: f64,
+    valid: bool,
+    format: Format,
+    output: Option<String>,
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+    value.parse().map_err(|_| Error::invalid(format!("{} expects a number, got {:?}", flag, value)))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>> {
+    let mut opts = Options {
+        rows: 5,
+        cols: 5,
+        width: 11,
+        height: 11,
+        seed: rng::random_seed(),
+        count: 1,
+        algorithms: vec![Algorithm::RecursiveBacktracker],
+        braid: 0.0,
+        valid: false,
+        format: Format::Ascii,
+        output: None,
+    };
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            return Ok(None);
+        }
+        if flag == "--valid" {
+            opts.valid = true;
+            continue;
+        }
+        let value = args.next().ok_or_else(|| Error::invalid(format!("{} needs a value", flag)))?;
+        match flag.as_str() {
+            "--rows" => opts.rows = number(&flag, &value)?,
+            "--cols" => opts.cols = number(&flag, &value)?,
+            "--room-size" => {
+                let (w, h) = value.split_once('x').unwrap_or((&value, &value));
+                opts.width = number(&flag, w)?;
+                opts.height = number(&flag, h)?;
+            }
+            "--seed" => opts.seed = number(&flag, &value)?,
+            "--count" => opts.count = number(&flag, &value)?,
+            "--algorithm" => {
+                opts.algorithms = if value == "all" {
+                    Algorithm::ALL.to_vec()
+                } else {
+                    value.split(',').map(Algorithm::parse).collect::<Result<_>>()?
+                };
+            }
+            "--braid" => opts.braid = number(&flag, &value)?,
+            "--format" => {
+                opts.format = match value.as_str() {
+                    "ascii" => Format::Ascii,
+                    "json" => Format::Json,
+                    "stats" => Format::Stats,
+                    _ => return Err(Error::invalid(format!("Unknown format: {}", value))),
+                }
+            }
+            "--output" => opts.output = Some(value),
+            _ => return Err(Error::invalid(format!("Unknown option: {}", flag))),
+        }
+    }
+    if opts.rows == 0 || opts.cols == 0 || opts.width == 0 || opts.height == 0 {
+        return Err(Error::invalid("Rows, cols and room size must be at least 1"));
+    }
+    if !(0.0..=1.0).contains(&opts.braid) {
+        return Err(Error::invalid(format!("Braid ratio must be between 0 and 1, got {}", opts.braid)));
+    }
+    Ok(Some(opts))
+}
+
+fn build(opts: &Options, seed: u64) -> Result<Dungeon> {
+    // Same spawn point as the pygame frontend: the middle of the middle room
+    let player = Entity::new(opts.width / 2, opts.height / 2, 10, 0, 1, 0, true, 0);
+    let algorithms = Some(opts.algorithms.clone());
+    if opts.valid {
+        Dungeon::generate_valid(opts.rows, opts.cols, opts.width, opts.height, player, Some(seed), algorithms, None, opts.braid, None, 10)
+    } else {
+        Dungeon::new(opts.rows, opts.cols, opts.width, opts.height, player, Some(seed), algorithms, None, opts.braid, None)
+    }
+}
+
+fn dead_ends(maze: &Maze) -> usize {
+    (0..maze.height)
+        .flat_map(|row| (0..maze.width).map(move |col| (row, col)))
+        .filter_map(|(row, col)| maze.get_cell_walls(row, col).ok())
+        .filter(|&(a, b, c, d)| [a, b, c, d].iter().filter(|&&wall| wall).count() == 3)
+        .count()
+}
+
+fn write_ascii(out: &mut impl Write, dungeon: &Dungeon, batch: bool) -> io::Result<()> {
+    if batch {
+        writeln!(out, "== seed {} ==", dungeon.seed)?;
+    }
+    for (r, row) in dungeon.mazes.iter().enumerate() {
+        for (c, maze) in row.iter().enumerate() {
+            writeln!(out, "room ({}, {})", r, c)?;
+            write!(out, "{}", maze.to_ascii(true, None, None))?;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+struct DungeonJson<R> {
+    seed: u64,
+    rows: usize,
+    cols: usize,
+    exit_room: (usize, usize),
+    exit_cell: (usize, usize),
+    keys_required: u32,
+    boss_rooms: Vec<(usize, usize)>,
+    shops: Vec<(usize, usize)>,
+    doors: Vec<DoorJson>,
+    rooms: Vec<RoomJson<R>>,
+}
+
+#[derive(Serialize)]
+struct DoorJson {
+    from: (usize, usize),
+    to: (usize, usize),
+    kind: String,
+}
+
+#[derive(Serialize)]
+struct RoomJson<R> {
+    row: usize,
+    col: usize,
+    maze: R,
+}
+
+fn write_json(out: &mut impl Write, dungeon: &Dungeon, pretty: bool) -> io::Result<()> {
+    let doc = DungeonJson {
+        seed: dungeon.seed,
+        rows: dungeon.mazes.len(),
+        cols: dungeon.mazes.first().map_or(0, Vec::len),
+        exit_room: dungeon.exit_room,
+        exit_cell: dungeon.exit_cell,
+        keys_required: dungeon.keys_required,
+        boss_rooms: dungeon.boss_rooms.clone(),
+        shops: dungeon.shops.iter().map(|s| s.room).collect(),
+        doors: dungeon
+            .doors
+            .iter()
+            .map(|d| DoorJson { from: d.from_room, to: d.to_room, kind: format!("{:?}", d.kind) })
+            .collect(),
+        rooms: dungeon
+            .mazes
+            .iter()
+            .enumerate()
+            .flat_map(|(r, row)| row.iter().enumerate().map(move |(c, maze)| RoomJson { row: r, col: c, maze: json::document(maze) }))
+            .collect(),
+    };
+    let text = if pretty { serde_json::to_string_pretty(&doc) } else { serde_json::to_string(&doc) };
+    writeln!(out, "{}", text.map_err(io::Error::other)?)
+}
+
+const STATS_HEADER: &str = "seed\trooms\tcells\tdead_ends\tchests\tgold\tkeys\tkeys_required\tlocked\tone_way\tsealed\tshops\tbosses\tproblems";
+
+fn write_stats(out: &mut impl Write, dungeon: &Dungeon) -> io::Result<()> {
+    let mazes: Vec<&Maze> = dungeon.mazes.iter().flatten().collect();
+    let chests: Vec<_> = mazes.iter().flat_map(|m| &m.chests).collect();
+    let gold: u32 = chests
+        .iter()
+        .filter_map(|c| match c.contents {
+            Some(ChestContent::Gold { amount }) => Some(amount),
+            _ => None,
+        })
+        .sum();
+    let keys = chests.iter().filter(|c| matches!(c.contents, Some(ChestContent::Key { }))).count();
+    let doors = |kind| dungeon.doors.iter().filter(|d| d.kind == kind).count();
+    writeln!(
+        out,
+        "{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}\t{}",
+        dungeon.seed,
+        mazes.len(),
+        mazes.iter().map(|m| m.width * m.height).sum::<usize>(),
+        mazes.iter().map(|m| dead_ends(m)).sum::<usize>(),
+        chests.len(),
+        gold,
+        keys,
+        dungeon.keys_required,
+        doors(DoorKind::Locked),
+        doors(DoorKind::OneWay),
+        doors(DoorKind::Sealed),
+        dungeon.shops.len(),
+        dungeon.boss_rooms.len(),
+        dungeon.validate().len(),
+    )
+}
+
+fn run(opts: &Options) -> Result<()> {
+    let out: Box<dyn Write> = match &opts.output {
+        Some(path) => Box::new(File::create(path)?),
+        None => Box::new(io::stdout().lock()),
+    };
+    let mut out = BufWriter::new(out);
+    let batch = opts.count > 1;
+    if opts.format == Format::Stats {
+        writeln!(out, "{}", STATS_HEADER)?;
+    }
+    for i in 0..opts.count {
+        let dungeon = build(opts, opts.seed.wrapping_add(i))?;
+        match opts.format {
+            Format::Ascii => write_ascii(&mut out, &dungeon, batch)?,
+            Format::Json => write_json(&mut out, &dungeon, !batch)?,
+            Format::Stats => write_stats(&mut out, &dungeon)?,
+        }
+    }
+    out.flush()?;
+    Ok(())
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(opts)) => opts,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("dungeon-gen: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    match run(&opts) {
+        Ok(()) => ExitCode::SUCCESS,
+        // Piping into `head` closes stdout early; that's not an error worth reporting
+        Err(Error::Io(err)) if err.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("dungeon-gen: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
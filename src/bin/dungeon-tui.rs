@@ -0,0 +1,491 @@
+// --- dungeon-tui: play in a terminal ---
+//
+// A turn-based frontend on top of the same `Dungeon` the pygame script drives, drawn
+// with crossterm. The current room uses the `Maze::display` drawing with fog of war on
+// top; the panel on the right holds the stats, the bag, a legend and the minimap.
+//
+//   arrows / wasd   move (walking into an enemy attacks it)
+//   p               drink a potion
+//   n               new dungeon
+//   q / Esc         quit
+//
+// `--moves` plays a fixed sequence of moves (u, d, l, r, p) without touching the
+// terminal and prints the last frame, which is what `tests/tui.rs` runs.
+use std::io::{self, IsTerminal, Write};
+use std::process::ExitCode;
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use crossterm::style::{Color, Print, ResetColor, SetForegroundColor};
+use crossterm::terminal::{self, ClearType};
+use crossterm::{cursor, execute, queue};
+use dungeon_core::dungeon::Dungeon;
+use dungeon_core::dungeon::door::DoorKind;
+use dungeon_core::dungeon::state::GameState;
+use dungeon_core::entity::Entity;
+use dungeon_core::entity::inventory::{Item, Slot};
+use dungeon_core::entity::kind::EnemyKind;
+use dungeon_core::rng;
+use dungeon_core::{Error, Result};
+
+const USAGE: &str = "\
+Usage: dungeon-tui [options]
+
+Options:
+  --rows N          rooms per column (default 5)
+  --cols N          rooms per row (default 5)
+  --room-size WxH   cells per room, or a single number for square rooms (default 11x11)
+  --seed N          dungeon seed (default: random)
+  --moves MOVES     play MOVES (u, d, l, r, p) without a terminal and print the last frame
+  -h, --help        show this help";
+
+/// Columns between the room drawing and the side panel
+const GUTTER: usize = 3;
+const WALL: Color = Color::Grey;
+const REMEMBERED: Color = Color::DarkGrey;
+
+struct Options {
+    rows: usize,
+    cols: usize,
+    width: usize,
+    height: usize,
+    seed: u64,
+    moves: Option<String>,
+}
+
+fn number<T: std::str::FromStr>(flag: &str, value: &str) -> Result<T> {
+    value.parse().map_err(|_| Error::invalid(format!("{} expects a number, got {:?}", flag, value)))
+}
+
+fn parse_args(args: impl IntoIterator<Item = String>) -> Result<Option<Options>> {
+    let mut opts = Options { rows: 5, cols: 5, width: 11, height: 11, seed: rng::random_seed(), moves: None };
+    let mut args = args.into_iter();
+    while let Some(flag) = args.next() {
+        if flag == "-h" || flag == "--help" {
+            return Ok(None);
+        }
+        let value = args.next().ok_or_else(|| Error::invalid(format!("{} needs a value", flag)))?;
+        match flag.as_str() {
+            "--rows" => opts.rows = number(&flag, &value)?,
+            "--cols" => opts.cols = number(&flag, &value)?,
+            "--room-size" => {
+                let (w, h) = value.split_once('x').unwrap_or((&value, &value));
+                opts.width = number(&flag, w)?;
+                opts.height = number(&flag, h)?;
+            }
+            "--seed" => opts.seed = number(&flag, &value)?,
+            "--moves" => opts.moves = Some(value),
+            _ => return Err(Error::invalid(format!("Unknown option: {}", flag))),
+        }
+    }
+    if opts.rows == 0 || opts.cols == 0 || opts.width == 0 || opts.height == 0 {
+        return Err(Error::invalid("Rows, cols and room size must be at least 1"));
+    }
+    Ok(Some(opts))
+}
+
+/// Something the player can do in one key press.
+#[derive(Clone, Copy)]
+enum Action {
+    Move(&'static str),
+    Potion,
+}
+
+/// A game in progress plus the line of feedback under the map.
+struct Game {
+    dungeon: Dungeon,
+    message: String,
+}
+
+impl Game {
+    fn new(opts: &Options, seed: u64) -> Result<Game> {
+        // Same spawn point and starting stats as the pygame frontend
+        let player = Entity::new(opts.width / 2, opts.height / 2, 1, 0, 1, 0, true, 0);
+        let dungeon = Dungeon::new(opts.rows, opts.cols, opts.width, opts.height, player, Some(seed), None, None, 0.0, None)?;
+        Ok(Game { dungeon, message: format!("Seed {}. Find the keys and get out.", seed) })
+    }
+
+    fn act(&mut self, action: Action) {
+        let before = self.dungeon.player.clone();
+        let room = (self.dungeon.current_room_row, self.dungeon.current_room_col);
+        let was_unlocked = self.dungeon.exit_unlocked();
+        let result = match action {
+            Action::Move(direction) => self.dungeon.move_player(direction),
+            Action::Potion => self.dungeon.use_item(Item::Potion),
+        };
+        self.message = match result {
+            Err(err) => err.to_string(),
+            Ok(_) => self.describe(&before, room, was_unlocked),
+        };
+    }
+
+    /// Sum up what the last turn changed.
+    fn describe(&self, before: &Entity, room: (usize, usize), was_unlocked: bool) -> String {
+        let dungeon = &self.dungeon;
+        let player = &dungeon.player;
+        match dungeon.state {
+            GameState::Won => return "You escaped the dungeon! n = new dungeon, q = quit".to_string(),
+            GameState::Lost => return "You died. n = new dungeon, q = quit".to_string(),
+            _ => {}
+        }
+        let mut parts = Vec::new();
+        if (dungeon.current_room_row, dungeon.current_room_col) != room {
+            parts.push(format!("Entered room ({}, {})", dungeon.current_room_row, dungeon.current_room_col));
+        }
+        if player.gold > before.gold {
+            parts.push(format!("+{} gold", player.gold - before.gold));
+        }
+        for (item, count) in player.inventory.items() {
+            let had = before.inventory.count(item);
+            if count > had {
+                parts.push(format!("Picked up {:?}", item));
+            }
+        }
+        for slot in [Slot::Weapon, Slot::Shield] {
+            if let Some(item) = player.inventory.equipped(slot)
+                && before.inventory.equipped(slot) != Some(item)
+            {
+                parts.push(format!("Equipped {:?}", item));
+            }
+        }
+        if player.health < before.health {
+            parts.push(format!("Took {} damage", before.health - player.health));
+        } else if player.health > before.health {
+            parts.push(format!("Healed {}", player.health - before.health));
+        }
+        if dungeon.exit_unlocked() && !was_unlocked {
+            parts.push("The exit is open".to_string());
+        }
+        parts.join(". ")
+    }
+}
+
+/// A grid of coloured characters, drawn in one go.
+struct Frame {
+    lines: Vec<Vec<(char, Color)>>,
+}
+
+impl Frame {
+    fn new() -> Frame {
+        Frame { lines: Vec::new() }
+    }
+
+    fn put(&mut self, row: usize, col: usize, ch: char, color: Color) {
+        if self.lines.len() <= row {
+            self.lines.resize(row + 1, Vec::new());
+        }
+        let line = &mut self.lines[row];
+        if line.len() <= col {
+            line.resize(col + 1, (' ', Color::Reset));
+        }
+        line[col] = (ch, color);
+    }
+
+    fn text(&mut self, row: usize, col: usize, text: &str, color: Color) {
+        for (i, ch) in text.chars().enumerate() {
+            self.put(row, col + i, ch, color);
+        }
+    }
+
+    fn plain(&self) -> String {
+        self.lines
+            .iter()
+            .map(|line| line.iter().map(|&(ch, _)| ch).collect::<String>().trim_end().to_string() + "\n")
+            .collect()
+    }
+
+    fn show(&self, out: &mut impl Write) -> io::Result<()> {
+        queue!(out, cursor::MoveTo(0, 0), terminal::Clear(ClearType::All))?;
+        for (row, line) in self.lines.iter().enumerate() {
+            queue!(out, cursor::MoveTo(0, row as u16))?;
+            let mut color = None;
+            for &(ch, c) in line {
+                if color != Some(c) {
+                    queue!(out, SetForegroundColor(c))?;
+                    color = Some(c);
+                }
+                queue!(out, Print(ch))?;
+            }
+        }
+        queue!(out, ResetColor)?;
+        out.flush()
+    }
+}
+
+fn enemy_glyph(kind: Option<EnemyKind>) -> char {
+    match kind {
+        Some(EnemyKind::Bat) => 'b',
+        Some(EnemyKind::Slime) => 's',
+        Some(EnemyKind::Slimeling) => 'o',
+        Some(EnemyKind::Skeleton) => 'k',
+        Some(EnemyKind::Ghost) => 'g',
+        Some(EnemyKind::Tank) => 't',
+        Some(EnemyKind::Boss) => 'B',
+        None => 'E',
+    }
+}
+
+/// Glyph and colour for a door seen from its `from_room` side, facing `to_room`.
+fn door_glyph(kind: DoorKind, from: (usize, usize), to: (usize, usize)) -> Option<(char, Color)> {
+    match kind {
+        DoorKind::Open => None,
+        DoorKind::Locked => Some(('#', Color::Yellow)),
+        DoorKind::Sealed => Some(('x', Color::Red)),
+        DoorKind::OneWay => Some((
+            match (to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize) {
+                (-1, _) => '^',
+                (1, _) => 'v',
+                (_, -1) => '<',
+                _ => '>',
+            },
+            Color::Blue,
+        )),
+    }
+}
+
+/// The current room, fogged: unexplored cells are blank, explored cells out of sight are dimmed.
+fn draw_room(frame: &mut Frame, dungeon: &Dungeon) {
+    let here = (dungeon.current_room_row, dungeon.current_room_col);
+    let maze = &dungeon.mazes[here.0][here.1];
+    let visible = dungeon.visible_cells();
+    let explored = maze.explored_cells();
+    let picture = maze.to_ascii(true, None, None);
+
+    // Each character of the drawing belongs to the one, two or four cells it touches
+    let cells = |line: usize, col: usize| {
+        let rows = if line % 2 == 1 { vec![line / 2] } else { vec![(line / 2).wrapping_sub(1), line / 2] };
+        let cols = if col.is_multiple_of(4) { vec![(col / 4).wrapping_sub(1), col / 4] } else { vec![col / 4] };
+        rows.into_iter()
+            .flat_map(move |r| cols.clone().into_iter().map(move |c| (r, c)))
+            .filter(|&(r, c)| r < maze.height && c < maze.width)
+    };
+    for (line, text) in picture.lines().enumerate() {
+        for (col, ch) in text.chars().enumerate() {
+            let (seen, lit) = cells(line, col).fold((false, false), |(s, l), (r, c)| (s || explored[r][c], l || visible[r][c]));
+            if !seen {
+                continue;
+            }
+            let color = match (line % 2 == 1 && col % 4 != 0, lit) {
+                (true, true) => Color::Yellow,
+                (false, true) => WALL,
+                (_, false) => REMEMBERED,
+            };
+            frame.put(line, col, ch, color);
+        }
+    }
+
+    let cell_centre = |x: usize, y: usize| (2 * y + 1, 4 * x + 2);
+    if here == dungeon.exit_room {
+        let (x, y) = dungeon.exit_cell;
+        if explored[y][x] {
+            let (line, col) = cell_centre(x, y);
+            frame.put(line, col, '>', if dungeon.exit_unlocked() { Color::Green } else { Color::Magenta });
+        }
+    }
+
+    // Doors sit in the wall gaps at the middle of each edge
+    for door in &dungeon.doors {
+        let (near, far) = if door.from_room == here {
+            (door.from_room, door.to_room)
+        } else if door.to_room == here {
+            (door.to_room, door.from_room)
+        } else {
+            continue;
+        };
+        let Some((glyph, color)) = door_glyph(door.kind, door.from_room, door.to_room) else { continue };
+        let (mid_row, mid_col) = (maze.height / 2, maze.width / 2);
+        let spots: Vec<(usize, usize)> = match (far.0 as isize - near.0 as isize, far.1 as isize - near.1 as isize) {
+            (-1, _) => (1..4).map(|i| (0, 4 * mid_col + i)).collect(),
+            (1, _) => (1..4).map(|i| (2 * maze.height, 4 * mid_col + i)).collect(),
+            (_, -1) => vec![(2 * mid_row + 1, 0)],
+            _ => vec![(2 * mid_row + 1, 4 * maze.width)],
+        };
+        for (line, col) in spots {
+            frame.put(line, col, glyph, color);
+        }
+    }
+
+    for enemy in &dungeon.enemies {
+        if visible.get(enemy.y).and_then(|r| r.get(enemy.x)).copied().unwrap_or(false) {
+            let (line, col) = cell_centre(enemy.x, enemy.y);
+            frame.put(line, col, enemy_glyph(enemy.kind), Color::Red);
+        }
+    }
+    let (line, col) = cell_centre(dungeon.player.x, dungeon.player.y);
+    frame.put(line, col, '@', Color::Cyan);
+}
+
+/// Stats, bag, legend and minimap, starting at column `left`.
+fn draw_panel(frame: &mut Frame, dungeon: &Dungeon, left: usize) {
+    let player = &dungeon.player;
+    let item = |slot| player.inventory.equipped(slot).map_or("-".to_string(), |i: Item| format!("{:?}", i));
+    let mut row = 0;
+    let mut line = |frame: &mut Frame, text: String, color: Color| {
+        frame.text(row, left, &text, color);
+        row += 1;
+    };
+    line(frame, format!("HP {}  ATK {}  ARM {}  Gold {}", player.health, player.attack, player.armor, player.gold), Color::White);
+    let exit = if dungeon.exit_unlocked() { "open" } else { "locked" };
+    line(frame, format!("Keys {}/{}  Exit {}", player.keys(), dungeon.keys_required, exit), Color::White);
+    line(frame, format!("Floor {}  Turn {}", dungeon.floor(), dungeon.turn), Color::White);
+    line(frame, format!("Weapon {}  Shield {}", item(Slot::Weapon), item(Slot::Shield)), Color::White);
+    let bag: Vec<_> = player.inventory.items().iter().map(|(i, n)| format!("{:?} x{}", i, n)).collect();
+    line(frame, format!("Bag: {}", if bag.is_empty() { "empty".to_string() } else { bag.join(", ") }), Color::White);
+    if let Some(phase) = dungeon.boss_phase {
+        line(frame, format!("Boss {:?}{}", phase, if dungeon.is_sealed() { "  room sealed" } else { "" }), Color::Red);
+    }
+    if let Some(shop) = dungeon.shop() {
+        let stock: Vec<_> = shop.stock.iter().map(|(i, n)| format!("{:?} x{}", i, n)).collect();
+        line(frame, format!("Merchant: {}", stock.join(", ")), Color::Yellow);
+    }
+    line(frame, String::new(), Color::Reset);
+    line(frame, "@ you  > exit  b s o k g t B enemies".to_string(), REMEMBERED);
+    line(frame, "# locked  x sealed  ^v<> one-way".to_string(), REMEMBERED);
+    line(frame, String::new(), Color::Reset);
+
+    // Minimap: one "[ ]" per room, door markers in between once either side is visited
+    let top = row;
+    let exploration = dungeon.exploration();
+    for (r, rooms) in dungeon.visited_rooms.iter().enumerate() {
+        for (c, &visited) in rooms.iter().enumerate() {
+            let (y, x) = (top + 2 * r, left + 4 * c);
+            let (mark, color) = if (r, c) == (dungeon.current_room_row, dungeon.current_room_col) {
+                ('@', Color::Green)
+            } else if !visited {
+                (' ', REMEMBERED)
+            } else if dungeon.boss_rooms.contains(&(r, c)) && !dungeon.defeated_bosses.contains(&(r, c)) {
+                ('B', Color::Red)
+            } else if (r, c) == dungeon.exit_room {
+                ('>', Color::Magenta)
+            } else if dungeon.shops.iter().any(|s| s.room == (r, c)) {
+                ('$', Color::Yellow)
+            } else if exploration[r][c] > 0.5 {
+                ('#', Color::White)
+            } else {
+                ('#', REMEMBERED)
+            };
+            frame.text(y, x, "[ ]", if visited { WALL } else { REMEMBERED });
+            frame.put(y, x + 1, mark, color);
+        }
+    }
+    for door in &dungeon.doors {
+        let ((r1, c1), (r2, c2)) = (door.from_room, door.to_room);
+        if !(dungeon.visited_rooms[r1][c1] || dungeon.visited_rooms[r2][c2]) {
+            continue;
+        }
+        if let Some((glyph, color)) = door_glyph(door.kind, door.from_room, door.to_room) {
+            let (y, x) = if r1 == r2 { (top + 2 * r1, left + 4 * c1.min(c2) + 3) } else { (top + 2 * r1.min(r2) + 1, left + 4 * c1 + 1) };
+            frame.put(y, x, glyph, color);
+        }
+    }
+}
+
+fn draw(game: &Game) -> Frame {
+    let dungeon = &game.dungeon;
+    let maze = &dungeon.mazes[dungeon.current_room_row][dungeon.current_room_col];
+    let mut frame = Frame::new();
+    draw_room(&mut frame, dungeon);
+    draw_panel(&mut frame, dungeon, 4 * maze.width + 1 + GUTTER);
+    let bottom = frame.lines.len().max(2 * maze.height + 1) + 1;
+    frame.text(bottom, 0, &game.message, Color::White);
+    frame
+}
+
+fn action_for(code: KeyCode) -> Option<Action> {
+    match code {
+        KeyCode::Up | KeyCode::Char('w') => Some(Action::Move("up")),
+        KeyCode::Down | KeyCode::Char('s') => Some(Action::Move("down")),
+        KeyCode::Left | KeyCode::Char('a') => Some(Action::Move("left")),
+        KeyCode::Right | KeyCode::Char('d') => Some(Action::Move("right")),
+        KeyCode::Char('p') => Some(Action::Potion),
+        _ => None,
+    }
+}
+
+/// Play `moves` without a terminal and print the last frame.
+fn replay(opts: &Options, moves: &str) -> Result<()> {
+    let mut game = Game::new(opts, opts.seed)?;
+    for ch in moves.chars().filter(|c| !c.is_whitespace()) {
+        let action = match ch {
+            'u' => Action::Move("up"),
+            'd' => Action::Move("down"),
+            'l' => Action::Move("left"),
+            'r' => Action::Move("right"),
+            'p' => Action::Potion,
+            _ => return Err(Error::invalid(format!("Unknown move {:?}, expected u, d, l, r or p", ch))),
+        };
+        game.act(action);
+    }
+    print!("{}", draw(&game).plain());
+    Ok(())
+}
+
+/// Puts the terminal back the way it was, even if the game panics.
+struct RawTerminal;
+
+impl RawTerminal {
+    fn enter() -> io::Result<RawTerminal> {
+        terminal::enable_raw_mode()?;
+        execute!(io::stdout(), terminal::EnterAlternateScreen, cursor::Hide)?;
+        Ok(RawTerminal)
+    }
+}
+
+impl Drop for RawTerminal {
+    fn drop(&mut self) {
+        let _ = execute!(io::stdout(), cursor::Show, terminal::LeaveAlternateScreen);
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+fn play(opts: &Options) -> Result<()> {
+    if !io::stdout().is_terminal() {
+        return Err(Error::invalid("Not running in a terminal; use --moves to play without one"));
+    }
+    let mut seed = opts.seed;
+    let mut game = Game::new(opts, seed)?;
+    let _terminal = RawTerminal::enter()?;
+    let mut out = io::stdout();
+    loop {
+        draw(&game).show(&mut out)?;
+        let Event::Key(key) = event::read()? else { continue };
+        if key.kind != KeyEventKind::Press {
+            continue;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return Ok(()),
+            KeyCode::Char('n') => {
+                seed = seed.wrapping_add(1);
+                game = Game::new(opts, seed)?;
+            }
+            code => {
+                if let Some(action) = action_for(code) {
+                    game.act(action);
+                }
+            }
+        }
+    }
+}
+
+fn main() -> ExitCode {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(opts)) => opts,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        }
+        Err(err) => {
+            eprintln!("dungeon-tui: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let result = match &opts.moves {
+        Some(moves) => replay(&opts, moves),
+        None => play(&opts),
+    };
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("dungeon-tui: {}", err);
+            ExitCode::FAILURE
+        }
+    }
+}
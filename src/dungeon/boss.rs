@@ -0,0 +1,192 @@
+// --- Boss rooms ---
+//
+// Every corner room except the spawn room is guarded by a boss. Walking in seals the
+// room's exits until the boss is dead. The fight runs in two phases:
+//
+//   Awake     slow, calls in an add every `BOSS_SUMMON_EVERY` turns
+//   Enraged   below half health: hits harder, moves every turn, summons twice as often
+//
+// The boss always drops one of the boss-only relics where it falls, or as close to it as
+// a chest fits.
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::entity::Entity;
+use crate::entity::kind::EnemyKind;
+use crate::maze::chest::{Chest, ChestContent};
+use crate::pathfinding::DistanceMap;
+use super::validate::openings;
+use super::{manhattan, open_step, Dungeon};
+
+/// Turns between summons while the boss is calm
+pub const BOSS_SUMMON_EVERY: u64 = 4;
+/// Adds the boss keeps around at most
+pub const BOSS_MAX_ADDS: usize = 3;
+/// Extra attack once the boss is enraged
+pub const BOSS_ENRAGE_ATTACK: i32 = 2;
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BossPhase {
+    Awake,
+    Enraged,
+}
+
+/// Boss-only loot, handed out in boss room order
+fn relic(index: usize) -> ChestContent {
+    match index % 2 {
+        0 => ChestContent::Greatsword { },
+        _ => ChestContent::Aegis { },
+    }
+}
+
+impl Dungeon {
+    /// Boss rooms: the corners, minus the spawn room in dungeons too small to tell them apart.
+    pub(super) fn boss_rooms_for(rows: usize, cols: usize) -> Vec<(usize, usize)> {
+        let mut rooms = vec![(0, 0), (0, cols - 1), (rows - 1, 0), (rows - 1, cols - 1)];
+        rooms.sort();
+        rooms.dedup();
+        rooms.retain(|&room| room != (rows / 2, cols / 2));
+        rooms
+    }
+
+    /// Whether the current room still has a living boss to beat.
+    pub(super) fn boss_pending(&self) -> bool {
+        let room = (self.current_room_row, self.current_room_col);
+        self.boss_rooms.contains(&room) && !self.defeated_bosses.contains(&room)
+    }
+
+    /// Put the boss on the chest-free cell farthest from where the player came in.
+    pub(super) fn spawn_boss(&mut self) {
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        let player = (self.player.x, self.player.y);
+        let (x, y) = (0..maze.height)
+            .flat_map(|y| (0..maze.width).map(move |x| (x, y)))
+            .filter(|&(x, y)| !maze.chests.iter().any(|c| (c.col, c.row) == (x, y)))
+            .max_by_key(|&(x, y)| manhattan(x, y, player.0, player.1))
+            .unwrap_or((0, 0));
+        self.enemies.clear();
+        self.enemies.push(Entity::enemy(EnemyKind::Boss, x, y, self.floor()));
+        self.boss_phase = Some(BossPhase::Awake);
+    }
+
+    /// The boss's scripted part of an enemy turn: enrage, then maybe summon an add.
+    pub(super) fn boss_turn(&mut self) {
+        let floor = self.floor();
+        let Some(boss) = self.enemies.iter_mut().find(|e| e.kind == Some(EnemyKind::Boss)) else {
+            return;
+        };
+        let (max_health, _, _) = EnemyKind::Boss.stats(floor);
+        if self.boss_phase == Some(BossPhase::Awake) && boss.health * 2 < max_health {
+            boss.attack += BOSS_ENRAGE_ATTACK;
+            self.boss_phase = Some(BossPhase::Enraged);
+        }
+        let every = match self.boss_phase {
+            Some(BossPhase::Enraged) => BOSS_SUMMON_EVERY / 2,
+            _ => BOSS_SUMMON_EVERY,
+        };
+        if !self.turn.is_multiple_of(every) || self.enemies.len() > BOSS_MAX_ADDS {
+            return;
+        }
+
+        let off_limits = self.off_limits();
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        let boss = self.enemies.iter().find(|e| e.kind == Some(EnemyKind::Boss)).expect("found above");
+        let pos = (boss.x, boss.y);
+        let spot = (0..4)
+            .filter_map(|dir| open_step(pos, dir, maze))
+            .filter(|spot| !off_limits.contains(spot))
+            .find(|&spot| spot != (self.player.x, self.player.y) && !self.enemies.iter().any(|e| (e.x, e.y) == spot));
+        if let Some((x, y)) = spot {
+            let kind = *EnemyKind::spawnable(floor).choose(&mut self.enemy_rng).expect("some kind can always spawn");
+            self.enemies.push(Entity::enemy(kind, x, y, floor));
+        }
+    }
+
+    /// Unseal the room and leave the relic on the nearest free cell to where the boss
+    /// fell. A cell is free if it holds no chest, isn't the player's and isn't a doorway;
+    /// if none is left the relic goes straight into the player's bag.
+    pub(super) fn defeat_boss(&mut self, x: usize, y: usize) {
+        let room = (self.current_room_row, self.current_room_col);
+        let index = self.boss_rooms.iter().position(|&r| r == room).unwrap_or(0);
+        let maze = &self.mazes[room.0][room.1];
+        let doorways: Vec<_> = (0..4).flat_map(|dir| openings(maze, dir)).collect();
+        let distances = DistanceMap::new(maze, &[(x, y)]);
+        let spot = (0..maze.height)
+            .flat_map(|row| (0..maze.width).map(move |col| (col, row)))
+            .filter(|&cell| cell != (self.player.x, self.player.y) && !doorways.contains(&cell))
+            .filter(|&(col, row)| !maze.chests.iter().any(|c| (c.col, c.row) == (col, row)))
+            .filter_map(|cell| distances.get(cell).map(|d| (d, cell)))
+            .min();
+        match spot {
+            Some((_, (col, row))) => self.mazes[room.0][room.1].chests.push(Chest::new(row, col, Some(relic(index)))),
+            None => self.player.pick_up(relic(index)),
+        }
+        self.defeated_bosses.push(room);
+        self.boss_phase = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_dungeon;
+    use super::super::validate::landing;
+    use crate::maze::chest::Chest;
+
+    /// Walk into the top-left boss room through its right-hand opening
+    fn enter_boss_room(dungeon: &mut Dungeon) -> (usize, usize) {
+        let door = openings(&dungeon.mazes[0][0], 1)[0];
+        dungeon.leave_room();
+        dungeon.current_room_row = 0;
+        dungeon.current_room_col = 0;
+        dungeon.player.move_to(door.0, door.1);
+        dungeon.enter_room();
+        door
+    }
+
+    #[test]
+    fn base_player_beats_the_boss_and_leaves() {
+        let mut dungeon = test_dungeon(3);
+        dungeon.doors.clear();
+        dungeon.player.health = 1000;
+        // No chests, so nothing on the way hands out a better weapon
+        dungeon.mazes[0][0].chests.clear();
+        let door = enter_boss_room(&mut dungeon);
+        assert!(dungeon.is_sealed());
+
+        for _ in 0..500 {
+            let Some(boss) = dungeon.enemies.iter().find(|e| e.kind == Some(EnemyKind::Boss)) else { break };
+            let route = dungeon.route(((0, 0), (dungeon.player.x, dungeon.player.y)), ((0, 0), (boss.x, boss.y))).unwrap();
+            dungeon.move_player(route.and_then(|r| r.first_move()).expect("the boss is in reach")).unwrap();
+        }
+        assert!(!dungeon.is_sealed());
+        assert_eq!(dungeon.defeated_bosses, vec![(0, 0)]);
+
+        let outside = ((0, 1), landing(&dungeon.mazes[0][1], door, 1));
+        while (dungeon.current_room_row, dungeon.current_room_col) == (0, 0) {
+            let here = ((0, 0), (dungeon.player.x, dungeon.player.y));
+            let route = dungeon.route(here, outside).unwrap().expect("the room is open again");
+            dungeon.move_player(route.first_move().unwrap()).unwrap();
+        }
+        assert_eq!((dungeon.current_room_row, dungeon.current_room_col), (0, 1));
+        assert_eq!(dungeon.player.attack, 1);
+    }
+
+    #[test]
+    fn boss_does_not_spawn_on_a_chest() {
+        let mut dungeon = test_dungeon(3);
+        let (width, height) = (dungeon.mazes[0][0].width, dungeon.mazes[0][0].height);
+        let door = openings(&dungeon.mazes[0][0], 1)[0];
+        // Chests everywhere but the doorway and the top-left cell
+        dungeon.mazes[0][0].chests = (0..height)
+            .flat_map(|row| (0..width).map(move |col| (row, col)))
+            .filter(|&(row, col)| (col, row) != door && (col, row) != (0, 0))
+            .map(|(row, col)| Chest::new(row, col, None))
+            .collect();
+        enter_boss_room(&mut dungeon);
+        let boss = &dungeon.enemies[0];
+        assert_eq!((boss.x, boss.y), (0, 0));
+    }
+}
@@ -0,0 +1,321 @@
+// --- Doors between rooms ---
+//
+// Neighbouring rooms are joined through the middle of their shared edge. Doors on those
+// passages are listed on the dungeon; a pair of rooms with no door listed is open.
+//
+//   Open     free to walk through (an unlocked door stays listed as Open)
+//   Locked   costs one key, then stays open
+//   OneWay   only from `from_room` to `to_room`
+//   Sealed   never opens
+//
+// Generation keeps a random spanning tree of the rooms passable, so sealing only ever
+// closes loops. Every locked door gets its own extra key, put in a room reachable without
+// opening anything, so door keys can't eat into the corner keys the exit needs. One-way
+// doors only go between rooms reachable without keys, and only where every one of those
+// can still walk back to spawn. `solve` then plays the dungeon through to confirm it can be won.
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use crate::entity::inventory::Item;
+use crate::maze::Maze;
+use crate::maze::chest::ChestContent;
+use crate::rng::{self, Stream};
+use super::Dungeon;
+
+/// Room position as (row, col)
+pub type Room = (usize, usize);
+
+/// One locked door for roughly this many rooms
+pub const ROOMS_PER_LOCK: usize = 8;
+/// Chance that a passage closing a loop is sealed
+pub const SEALED_CHANCE: f64 = 0.2;
+/// Chance that a passage between rooms reachable without keys becomes one-way
+pub const ONE_WAY_CHANCE: f64 = 0.15;
+/// Layouts rolled before giving up and leaving every passage open
+const MAX_ATTEMPTS: usize = 16;
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DoorKind {
+    Open,
+    Locked,
+    OneWay,
+    Sealed,
+}
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core", get_all))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Door {
+    pub from_room: Room,
+    pub to_room: Room,
+    pub kind: DoorKind,
+}
+
+impl Door {
+    /// Whether the door lets someone walk through from `room` as it stands, keys aside
+    pub fn passable_from(&self, room: Room) -> bool {
+        match self.kind {
+            DoorKind::Open => true,
+            DoorKind::OneWay => room == self.from_room,
+            DoorKind::Locked | DoorKind::Sealed => false,
+        }
+    }
+
+    /// Whether the door joins `a` and `b`, in either direction
+    pub fn joins(&self, a: Room, b: Room) -> bool {
+        (self.from_room, self.to_room) == (a, b) || (self.from_room, self.to_room) == (b, a)
+    }
+}
+
+/// Rooms next door to `room` in a `rows` x `cols` grid
+fn neighbours(rows: usize, cols: usize, (r, c): Room) -> impl Iterator<Item = Room> {
+    [
+        (r > 0).then(|| (r - 1, c)),
+        (c + 1 < cols).then_some((r, c + 1)),
+        (r + 1 < rows).then_some((r + 1, c)),
+        (c > 0).then(|| (r, c - 1)),
+    ]
+    .into_iter()
+    .flatten()
+}
+
+fn passable(doors: &[Door], from: Room, to: Room) -> bool {
+    doors.iter().find(|d| d.joins(from, to)).is_none_or(|d| d.passable_from(from))
+}
+
+/// Rooms reachable from `start` without keys. With `backwards`, rooms that can reach `start` instead.
+fn reach(rows: usize, cols: usize, doors: &[Door], start: Room, backwards: bool) -> Vec<Vec<bool>> {
+    let mut seen = vec![vec![false; cols]; rows];
+    seen[start.0][start.1] = true;
+    let mut queue = VecDeque::from([start]);
+    while let Some(room) = queue.pop_front() {
+        for next in neighbours(rows, cols, room) {
+            let open = if backwards { passable(doors, next, room) } else { passable(doors, room, next) };
+            if open && !seen[next.0][next.1] {
+                seen[next.0][next.1] = true;
+                queue.push_back(next);
+            }
+        }
+    }
+    seen
+}
+
+/// Key chests still closed in every room, indexed [row][col]
+fn keys_in(mazes: &[Vec<Maze>]) -> Vec<Vec<u32>> {
+    let is_key = |contents: &Option<ChestContent>| matches!(contents, Some(ChestContent::Key { }));
+    mazes
+        .iter()
+        .map(|row| row.iter().map(|m| m.chests.iter().filter(|c| !c.is_open && is_key(&c.contents)).count() as u32).collect())
+        .collect()
+}
+
+/// Play the dungeon through from `start` holding `keys`: walk everywhere reachable, pick up
+/// every key there, open a locked door at the edge, repeat. Solvable if that reaches every
+/// room and still leaves `keys_required` keys in hand.
+pub fn solve(doors: &[Door], room_keys: &[Vec<u32>], start: Room, keys: u32, keys_required: u32) -> bool {
+    let (rows, cols) = (room_keys.len(), room_keys.first().map_or(0, Vec::len));
+    let mut doors = doors.to_vec();
+    let mut keys = keys;
+    let mut collected = vec![vec![false; cols]; rows];
+    loop {
+        let reached = reach(rows, cols, &doors, start, false);
+        for (r, row) in reached.iter().enumerate() {
+            for (c, &here) in row.iter().enumerate() {
+                if here && !collected[r][c] {
+                    collected[r][c] = true;
+                    keys += room_keys[r][c];
+                }
+            }
+        }
+        let frontier = doors.iter_mut().find(|d| {
+            d.kind == DoorKind::Locked && (reached[d.from_room.0][d.from_room.1] || reached[d.to_room.0][d.to_room.1])
+        });
+        match frontier {
+            Some(door) if keys > 0 => {
+                keys -= 1;
+                door.kind = DoorKind::Open;
+            }
+            _ => return reached.iter().flatten().all(|&r| r) && keys >= keys_required,
+        }
+    }
+}
+
+impl Dungeon {
+    /// Roll the doors for a freshly built dungeon and put their keys into the rooms,
+    /// never on `start`, the player's (x, y) in the spawn room.
+    pub(super) fn doors_for(seed: u64, mazes: &mut [Vec<Maze>], spawn: Room, start: (usize, usize), keys: u32, keys_required: u32) -> Vec<Door> {
+        let (rows, cols) = (mazes.len(), mazes[0].len());
+        let mut rng = rng::stream_rng(seed, Stream::Doors);
+        for _ in 0..MAX_ATTEMPTS {
+            // Random spanning tree grown out from spawn, each edge pointing away from it
+            let mut in_tree = vec![vec![false; cols]; rows];
+            in_tree[spawn.0][spawn.1] = true;
+            let mut tree: Vec<(Room, Room)> = Vec::new();
+            let mut stack = vec![spawn];
+            while let Some(&room) = stack.last() {
+                let mut fresh: Vec<_> = neighbours(rows, cols, room).filter(|n| !in_tree[n.0][n.1]).collect();
+                fresh.shuffle(&mut rng);
+                match fresh.first() {
+                    Some(&next) => {
+                        in_tree[next.0][next.1] = true;
+                        tree.push((room, next));
+                        stack.push(next);
+                    }
+                    None => {
+                        stack.pop();
+                    }
+                }
+            }
+            let on_tree = |a: Room, b: Room| tree.iter().any(|&(p, q)| (p, q) == (a, b) || (p, q) == (b, a));
+
+            let mut doors = Vec::new();
+            for r in 0..rows {
+                for c in 0..cols {
+                    for next in [(r, c + 1), (r + 1, c)] {
+                        if next.0 < rows && next.1 < cols && !on_tree((r, c), next) && rng.gen_bool(SEALED_CHANCE) {
+                            doors.push(Door { from_room: (r, c), to_room: next, kind: DoorKind::Sealed });
+                        }
+                    }
+                }
+            }
+            let mut lockable = tree.clone();
+            lockable.shuffle(&mut rng);
+            for &(from_room, to_room) in lockable.iter().take(rows * cols / ROOMS_PER_LOCK) {
+                doors.push(Door { from_room, to_room, kind: DoorKind::Locked });
+            }
+
+            let free = reach(rows, cols, &doors, spawn, false);
+            let is_free = |room: Room| free[room.0][room.1];
+            for r in 0..rows {
+                for c in 0..cols {
+                    for next in [(r, c + 1), (r + 1, c)] {
+                        if next.0 >= rows
+                            || next.1 >= cols
+                            || !is_free((r, c))
+                            || !is_free(next)
+                            || doors.iter().any(|d| d.joins((r, c), next))
+                            || !rng.gen_bool(ONE_WAY_CHANCE)
+                        {
+                            continue;
+                        }
+                        let (from_room, to_room) = if rng.gen_bool(0.5) { ((r, c), next) } else { (next, (r, c)) };
+                        doors.push(Door { from_room, to_room, kind: DoorKind::OneWay });
+                        // Undo it if it cuts a room off from spawn in either direction
+                        let there = reach(rows, cols, &doors, spawn, false);
+                        let back = reach(rows, cols, &doors, spawn, true);
+                        let trapped = (0..rows).any(|r| (0..cols).any(|c| free[r][c] && !(there[r][c] && back[r][c])));
+                        if trapped {
+                            doors.pop();
+                        }
+                    }
+                }
+            }
+
+            // One extra key per lock, in rooms reachable without keys that don't hold one yet
+            let mut room_keys = keys_in(mazes);
+            let mut key_rooms: Vec<Room> = (0..rows)
+                .flat_map(|r| (0..cols).map(move |c| (r, c)))
+                .filter(|&room| is_free(room) && room_keys[room.0][room.1] == 0)
+                .collect();
+            key_rooms.shuffle(&mut rng);
+            let locks = doors.iter().filter(|d| d.kind == DoorKind::Locked).count();
+            if key_rooms.len() < locks {
+                continue;
+            }
+            key_rooms.truncate(locks);
+            for &(r, c) in &key_rooms {
+                room_keys[r][c] += 1;
+            }
+
+            if solve(&doors, &room_keys, spawn, keys, keys_required) {
+                for (r, c) in key_rooms {
+                    let avoid = if (r, c) == spawn { vec![(start.1, start.0)] } else { Vec::new() };
+                    mazes[r][c].place_key(rng::room_seed(seed, r, c), &avoid);
+                }
+                return doors;
+            }
+        }
+        Vec::new()
+    }
+
+    /// Try to get through the door into `to`, spending a key on a locked one.
+    pub(super) fn pass_door(&mut self, to: Room) -> bool {
+        let here = (self.current_room_row, self.current_room_col);
+        let has_key = self.player.keys() > 0;
+        let Some(door) = self.doors.iter_mut().find(|d| d.joins(here, to)) else {
+            return true;
+        };
+        match door.kind {
+            DoorKind::Locked if has_key => {
+                door.kind = DoorKind::Open;
+                self.player.inventory.remove(Item::Key, 1).expect("checked above");
+                true
+            }
+            _ => door.passable_from(here),
+        }
+    }
+
+    /// Key chests still waiting in each room, for the solver
+    pub(super) fn room_keys(&self) -> Vec<Vec<u32>> {
+        keys_in(&self.mazes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn door(from_room: Room, to_room: Room, kind: DoorKind) -> Door {
+        Door { from_room, to_room, kind }
+    }
+
+    #[test]
+    fn solver_follows_the_doors() {
+        let none = vec![vec![0, 0, 0]];
+        assert!(solve(&[], &none, (0, 0), 0, 0));
+        assert!(!solve(&[door((0, 0), (0, 1), DoorKind::Sealed)], &none, (0, 0), 0, 0));
+        assert!(!solve(&[door((0, 1), (0, 0), DoorKind::OneWay)], &none, (0, 0), 0, 0));
+        assert!(solve(&[door((0, 0), (0, 1), DoorKind::OneWay)], &none, (0, 0), 0, 0));
+    }
+
+    #[test]
+    fn locked_doors_spend_keys() {
+        let locked = [door((0, 1), (0, 2), DoorKind::Locked)];
+        let key_at_start = vec![vec![1, 0, 0]];
+        assert!(solve(&locked, &key_at_start, (0, 0), 0, 0));
+        // The only key opens the door, so none is left for the exit
+        assert!(!solve(&locked, &key_at_start, (0, 0), 0, 1));
+        // A key behind the lock doesn't help open it
+        assert!(!solve(&locked, &[vec![0, 0, 1]], (0, 0), 0, 0));
+    }
+
+    #[test]
+    fn exit_follows_the_keys_in_hand() {
+        let mut dungeon = super::super::test_dungeon(1);
+        dungeon.player.set_keys(dungeon.keys_required);
+        assert!(dungeon.exit_unlocked());
+        dungeon.doors = vec![door((1, 1), (0, 1), DoorKind::Locked)];
+        assert!(dungeon.pass_door((0, 1)));
+        assert_eq!(dungeon.player.keys(), dungeon.keys_required - 1);
+        assert!(!dungeon.exit_unlocked());
+    }
+
+    #[test]
+    fn nothing_starts_under_the_player() {
+        for seed in 0..100 {
+            let dungeon = super::super::test_dungeon(seed);
+            let start = (dungeon.player.y, dungeon.player.x);
+            assert!(dungeon.current_maze().chests.iter().all(|c| (c.row, c.col) != start), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn generated_doors_are_solvable() {
+        for seed in 0..20 {
+            assert!(super::super::test_dungeon(seed).solvable(), "seed {}", seed);
+        }
+    }
+}
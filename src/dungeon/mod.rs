@@ -1,33 +1,111 @@
+pub mod boss;
+pub mod door;
+pub mod room;
+pub mod route;
+pub mod shop;
+pub mod state;
+pub mod validate;
 
 use crate::maze::Maze;
-use crate::maze::chest::{Chest, ChestContent};
+use crate::maze::generator::Algorithm;
+use crate::maze::sight;
 use crate::entity::Entity;
+use crate::entity::inventory::{Item, Slot};
+use crate::entity::kind::{Attack, EnemyKind, Movement, ARCHER_RANGE};
+use crate::pathfinding::{open_step, DistanceMap, FlowField};
+use crate::render::{self, RenderOptions};
+use crate::rng::{self, Stream};
+use crate::save;
+use boss::BossPhase;
+use door::Door;
+use room::{Occupant, Respawn, RoomState};
+use shop::Shop;
+use state::GameState;
+use validate::Diagnostic;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
-use pyo3::Py;
-use pyo3::Python;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
 use rand::Rng;
-use std::collections::{BinaryHeap, HashMap, HashSet};
-use std::cmp::Ordering;
+use rand::seq::SliceRandom;
+use rand_chacha::ChaCha8Rng;
+use std::collections::{HashMap, HashSet};
 
-#[pyclass]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Dungeon {
-    #[pyo3(get, set)]
     pub mazes: Vec<Vec<Maze>>,
-    #[pyo3(get, set)]
     pub player: Entity,
-    #[pyo3(get, set)]
     pub current_room_row: usize,
-    #[pyo3(get, set)]
     pub current_room_col: usize,
-    #[pyo3(get, set)]
     pub enemies: Vec<Entity>,
+    pub seed: u64,
+    /// Rooms the player has stood in, indexed [row][col]
+    pub visited_rooms: Vec<Vec<bool>>,
+    /// Corner keys needed to open the exit door
+    pub keys_required: u32,
+    /// Room holding the exit door, as (row, col)
+    pub exit_room: (usize, usize),
+    /// Door cell inside the exit room, as (x, y)
+    pub exit_cell: (usize, usize),
+    pub state: GameState,
+    /// Enemy turns taken so far
+    pub turn: u64,
+    /// Rooms guarded by a boss, as (row, col)
+    pub boss_rooms: Vec<(usize, usize)>,
+    /// Boss rooms that have been cleared
+    pub defeated_bosses: Vec<(usize, usize)>,
+    /// Phase of the boss in the current room, if one is alive
+    pub boss_phase: Option<BossPhase>,
+    /// Doors on the passages between rooms; passages not listed are open
+    pub doors: Vec<Door>,
+    /// Merchants, one per shop room
+    pub shops: Vec<Shop>,
+    /// What happens to a room's enemies after the player leaves it
+    pub respawn: Respawn,
+    /// Enemies left behind in every room, indexed [row][col]
+    rooms: Vec<Vec<RoomState>>,
+    enemy_rng: ChaCha8Rng,
 }
 
-#[pymethods]
 impl Dungeon {
-    #[new]
-    pub fn new(rows: usize, cols: usize, maze_width: usize, maze_height: usize, player: Entity) -> Self {
+    /// Build a dungeon. Passing the same `seed` rebuilds the exact same rooms, chests and enemy spawns.
+    ///
+    /// `room_algorithms` picks the carving algorithm for single rooms, keyed by (row, col).
+    /// Every room not in it falls back to one of `algorithms`, picked from its room seed
+    /// (a single entry applies to every such room), and without those to the recursive
+    /// backtracker. `braid` is the fraction of dead ends opened into loops in every room.
+    /// `respawn` decides whether rooms restock their enemies; by default they never do.
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        maze_width: usize,
+        maze_height: usize,
+        player: Entity,
+        seed: Option<u64>,
+        algorithms: Option<Vec<Algorithm>>,
+        room_algorithms: Option<HashMap<(usize, usize), Algorithm>>,
+        braid: f64,
+        respawn: Option<Respawn>,
+    ) -> Result<Self> {
+        if rows == 0 || cols == 0 || maze_width == 0 || maze_height == 0 {
+            return Err(Error::invalid("Dungeon and room sizes must be at least 1"));
+        }
+        if player.x >= maze_width || player.y >= maze_height {
+            return Err(Error::invalid(format!("Player at ({}, {}) starts outside a {}x{} room", player.x, player.y, maze_width, maze_height)));
+        }
+        let room_algorithms = room_algorithms.unwrap_or_default();
+        if let Some(&(r, c)) = room_algorithms.keys().find(|&&(r, c)| r >= rows || c >= cols) {
+            return Err(Error::out_of_bounds(format!("Algorithm given for room ({}, {}) outside the dungeon", r, c)));
+        }
+        let seed = seed.unwrap_or_else(rng::random_seed);
+        let algorithms = algorithms
+            .filter(|a| !a.is_empty())
+            .unwrap_or_else(|| vec![Algorithm::RecursiveBacktracker]);
+        let mut corners = vec![(0, 0), (0, cols - 1), (rows - 1, 0), (rows - 1, cols - 1)];
+        corners.sort();
+        corners.dedup();
         let mut mazes = Vec::with_capacity(rows);
         for r in 0..rows {
             let mut row_vec = Vec::with_capacity(cols);
@@ -42,7 +120,7 @@ impl Dungeon {
                 let on_right = c == cols - 1;
 
                 // Corners: 3 exits, one is either "left" or "right" based on position
-                if (on_top && on_left) || (on_top && on_right) || (on_bottom && on_left) || (on_bottom && on_right) {
+                if (on_top || on_bottom) && (on_left || on_right) {
                     // Corner logic
                     if on_top && on_left {
                         exits.push("left".to_string());
@@ -88,233 +166,186 @@ impl Dungeon {
                     exits.push("right".to_string());
                 }
 
+                let room_seed = rng::room_seed(seed, r, c);
+                let algorithm = match room_algorithms.get(&(r, c)) {
+                    Some(&algorithm) => algorithm,
+                    None => algorithms[(room_seed % algorithms.len() as u64) as usize],
+                };
                 let mut maze = Maze::new(maze_width, maze_height);
                 // Pass exits to maze generation
-                maze.generate_maze(Some(exits)).expect("Failed to generate maze");
+                maze.generate_maze(Some(exits), Some(room_seed), Some(algorithm), braid)?;
+                // Nothing stands where the player starts
+                let start = if (r, c) == (rows / 2, cols / 2) { vec![(player.y, player.x)] } else { Vec::new() };
+                maze.chests.retain(|chest| !start.contains(&(chest.row, chest.col)));
+                if corners.contains(&(r, c)) {
+                    maze.place_key(room_seed, &start);
+                }
                 row_vec.push(maze);
             }
             mazes.push(row_vec);
         }
-        Dungeon {
+        let mut visited_rooms = vec![vec![false; cols]; rows];
+        visited_rooms[rows / 2][cols / 2] = true;
+        mazes[rows / 2][cols / 2].reveal(player.x, player.y);
+        let boss_rooms = Self::boss_rooms_for(rows, cols);
+        let doors = Self::doors_for(seed, &mut mazes, (rows / 2, cols / 2), (player.x, player.y), player.keys(), corners.len() as u32);
+        let exit_cell = exit_cell_for(&mazes[rows / 2][cols / 2], (player.x, player.y));
+        Ok(Dungeon {
             mazes,
             player,
             current_room_row: rows / 2,
             current_room_col: cols / 2,
             enemies: Vec::new(),
-        }
+            seed,
+            visited_rooms,
+            keys_required: corners.len() as u32,
+            exit_room: (rows / 2, cols / 2),
+            exit_cell,
+            state: GameState::Playing,
+            turn: 0,
+            doors,
+            shops: Self::shops_for(seed, rows, cols, &boss_rooms),
+            boss_rooms,
+            defeated_bosses: Vec::new(),
+            boss_phase: None,
+            respawn: respawn.unwrap_or_default(),
+            rooms: vec![vec![RoomState::default(); cols]; rows],
+            enemy_rng: rng::stream_rng(seed, Stream::Enemies),
+        })
     }
 
-    /// Call this when entering a new room
+    /// Call this when entering a new room. Enemies go on distinct cells away from the
+    /// player, closed chests and the exit; a room too small for all of them gets as many as fit.
     pub fn spawn_enemies(&mut self, count: usize) {
+        let off_limits = self.off_limits();
         let maze = &self.mazes[self.current_room_row][self.current_room_col];
-        let mut rng = rand::thread_rng();
+        let floor = self.floor();
+        let kinds = EnemyKind::spawnable(floor);
+        let rng = &mut self.enemy_rng;
+        let mut free: Vec<(usize, usize)> = (0..maze.height)
+            .flat_map(|y| (0..maze.width).map(move |x| (x, y)))
+            .filter(|&cell| cell != (self.player.x, self.player.y) && !off_limits.contains(&cell))
+            .collect();
         self.enemies.clear();
         for _ in 0..count {
-            // Random position not on player
-            let mut x;
-            let mut y;
-            loop {
-                x = rng.gen_range(0..maze.width);
-                y = rng.gen_range(0..maze.height);
-                if x != self.player.x || y != self.player.y {
-                    break;
-                }
+            if free.is_empty() {
+                break;
             }
-            // Calculate "floor" as the Manhattan distance from spawn room (middle) to current room
-            let spawn_row = self.mazes.len() / 2;
-            let spawn_col = self.mazes[0].len() / 2;
-            let floor = manhattan(self.current_room_col, self.current_room_row, spawn_col, spawn_row);
+            let (x, y) = free.swap_remove(rng.gen_range(0..free.len()));
+            // Deeper rooms unlock tougher kinds, and every kind scales with the floor
+            let kind = *kinds.choose(rng).expect("some kind can always spawn");
+            self.enemies.push(Entity::enemy(kind, x, y, floor));
+        }
+    }
 
-            // Scale enemy stats based on floor (stronger on higher floors/rings)
-            let base_health = 3;
-            let base_attack = 1;
-            let health = base_health + floor * 1;
-            let attack = base_attack + (floor / 2);
+    /// Cells of the current room no enemy may stand on: closed chests and the exit door
+    fn off_limits(&self) -> HashSet<(usize, usize)> {
+        let here = (self.current_room_row, self.current_room_col);
+        let chests = self.mazes[here.0][here.1].chests.iter().filter(|c| !c.is_open).map(|c| (c.col, c.row));
+        chests.chain((here == self.exit_room).then_some(self.exit_cell)).collect()
+    }
 
-            self.enemies.push(Entity {
-                x,
-                y,
-                health: health as i32,
-                armor: 0,
-                attack: attack as i32,
-                gold: 0,
-                is_player: false,
-            });
-        }
+    /// Manhattan distance from the spawn room (middle) to the current room
+    pub fn floor(&self) -> usize {
+        let spawn_row = self.mazes.len() / 2;
+        let spawn_col = self.mazes[0].len() / 2;
+        manhattan(self.current_room_col, self.current_room_row, spawn_col, spawn_row)
     }
 
+    /// One enemy turn: the boss acts, then every enemy moves by its kind's rule unless it
+    /// can already strike the player. Enemies don't fight each other; a step into a
+    /// taken cell just costs that enemy its move.
     pub fn move_enemies(&mut self) {
+        self.turn += 1;
+        self.boss_turn();
+        let off_limits = self.off_limits();
         let maze = &self.mazes[self.current_room_row][self.current_room_col];
         let player_pos = (self.player.x, self.player.y);
+        // One field towards the player, shared by everyone chasing them this turn
+        let chase = FlowField::new(maze, &DistanceMap::new(maze, &[player_pos]));
 
-        // Track where enemies will move to (not where they are now)
-        let mut planned_positions: HashSet<(usize, usize)> = HashSet::new();
-
-        // Collect original positions for pathfinding
-        let original_positions: Vec<(usize, usize)> = self.enemies.iter().map(|e| (e.x, e.y)).collect();
+        // Cells taken by enemies, updated as each one moves; nobody steps into another
+        let mut occupied: HashSet<(usize, usize)> = self.enemies.iter().map(|e| (e.x, e.y)).collect();
 
         for i in 0..self.enemies.len() {
-            // If enemy is adjacent to player, do not move
-            if is_adjacent(self.enemies[i].x, self.enemies[i].y, player_pos.0, player_pos.1, maze) {
-                planned_positions.insert((self.enemies[i].x, self.enemies[i].y));
+            // If enemy can already hit the player, do not move
+            if can_strike(&self.enemies[i], &self.player, maze) {
                 continue;
             }
 
-            // Check if adjacent to another enemy (not self)
-            let mut attacked = false;
-            for j in 0..self.enemies.len() {
-                if i != j {
-                    // Use split_at_mut to avoid double-borrow
-                    let (left, right) = if i < j {
-                        let (left, right) = self.enemies.split_at_mut(j);
-                        (&mut left[i], &mut right[0])
-                    } else {
-                        let (left, right) = self.enemies.split_at_mut(i);
-                        (&mut right[0], &mut left[j])
-                    };
-
-                    if is_adjacent(
-                        left.x,
-                        left.y,
-                        right.x,
-                        right.y,
-                        maze,
-                    ) {
-                        // Attack the other enemy (for demonstration, just reduce health)
-                        right.take_damage(left.attack);
-                        attacked = true;
-                        break;
-                    }
-                }
-            }
-            if attacked {
-                planned_positions.insert((self.enemies[i].x, self.enemies[i].y));
+            let pos = (self.enemies[i].x, self.enemies[i].y);
+            let movement = match self.enemies[i].kind {
+                Some(EnemyKind::Boss) if self.boss_phase == Some(BossPhase::Enraged) => Movement::Chase,
+                kind => kind.map_or(Movement::Chase, EnemyKind::movement),
+            };
+
+            // Slow movers sit out every other turn
+            if movement == Movement::Slow && self.turn % 2 == 1 {
                 continue;
             }
 
-            // A* pathfinding to player
-            fn astar<F, H>(
-                start: (usize, usize),
-                goal: (usize, usize),
-                mut neighbors: F,
-                mut heuristic: H,
-            ) -> Option<Vec<(usize, usize)>>
-            where
-                F: FnMut((usize, usize)) -> Vec<((usize, usize), usize)>,
-                H: FnMut((usize, usize)) -> usize,
-            {
-                #[derive(Eq)]
-                struct Node {
-                    pos: (usize, usize),
-                    cost: usize,
-                    est: usize,
-                }
-                impl Ord for Node {
-                    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
-                        (other.cost + other.est).cmp(&(self.cost + self.est))
-                    }
-                }
-                impl PartialOrd for Node {
-                    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
-                        Some(self.cmp(other))
-                    }
-                }
-                impl PartialEq for Node {
-                    fn eq(&self, other: &Self) -> bool {
-                        self.pos == other.pos && self.cost + self.est == other.cost + other.est
-                    }
+            let next = match movement {
+                Movement::Phase => Some(phase_step(pos, player_pos)),
+                Movement::Erratic if self.enemy_rng.gen_bool(0.5) => {
+                    let open: Vec<_> = (0..4).filter_map(|dir| open_step(pos, dir, maze)).collect();
+                    open.choose(&mut self.enemy_rng).copied()
                 }
+                _ => chase.next(pos),
+            };
 
-                let mut heap = BinaryHeap::new();
-                let mut came_from = HashMap::new();
-                let mut cost_so_far = HashMap::new();
-
-                heap.push(Node { pos: start, cost: 0, est: heuristic(start) });
-                cost_so_far.insert(start, 0);
-
-                while let Some(Node { pos, cost, .. }) = heap.pop() {
-                    if pos == goal {
-                        // Reconstruct path
-                        let mut path = vec![pos];
-                        let mut current = pos;
-                        while let Some(&prev) = came_from.get(&current) {
-                            path.push(prev);
-                            current = prev;
-                        }
-                        path.reverse();
-                        return Some(path);
-                    }
-                    for (next, step_cost) in neighbors(pos) {
-                        let new_cost = cost + step_cost;
-                        if cost_so_far.get(&next).map_or(true, |&c| new_cost < c) {
-                            cost_so_far.insert(next, new_cost);
-                            let est = heuristic(next);
-                            heap.push(Node { pos: next, cost: new_cost, est });
-                            came_from.insert(next, pos);
-                        }
-                    }
-                }
-                None
+            if let Some(next) = next
+                && next != player_pos
+                && !occupied.contains(&next)
+                && !off_limits.contains(&next)
+            {
+                self.enemies[i].x = next.0;
+                self.enemies[i].y = next.1;
+                occupied.remove(&pos);
+                occupied.insert(next);
             }
+        }
 
-            let path = astar(
-                (self.enemies[i].x, self.enemies[i].y),
-                player_pos,
-                |pos| {
-                    let mut neighbors = Vec::new();
-                    let directions = [
-                        (0isize, -1isize, 0),  // up
-                        (1, 0, 1),             // right
-                        (0, 1, 2),             // down
-                        (-1, 0, 3),            // left
-                    ];
-
-                    for &(dx, dy, dir_idx) in &directions {
-                        let nx = pos.0 as isize + dx;
-                        let ny = pos.1 as isize + dy;
-
-                        if nx < 0 || ny < 0 || nx >= maze.width as isize || ny >= maze.height as isize {
-                            continue;
-                        }
-
-                        if !maze.can_move(pos.1, pos.0, dir_idx) {
-                            continue;
-                        }
-
-                        let next_pos = (nx as usize, ny as usize);
-
-                        neighbors.push((next_pos, 1));
-                    }
+        // Remove dead enemies after all moves
+        self.remove_dead_enemies();
+    }
 
-                    neighbors
-                },
-                |pos| manhattan(pos.0, pos.1, player_pos.0, player_pos.1),
-            );
-
-            if let Some(path) = path {
-                if path.len() > 1 {
-                    let next = path[1];
-                    if !planned_positions.contains(&next) {
-                        self.enemies[i].x = next.0;
-                        self.enemies[i].y = next.1;
-                        planned_positions.insert(next);
-                    } else {
-                        planned_positions.insert((self.enemies[i].x, self.enemies[i].y));
-                    }
-                } else {
-                    planned_positions.insert((self.enemies[i].x, self.enemies[i].y));
-                }
-            } else {
-                planned_positions.insert((self.enemies[i].x, self.enemies[i].y));
+    /// Drop dead enemies. Slimes leave two slimelings behind where there is room,
+    /// and a fallen boss leaves its relic.
+    fn remove_dead_enemies(&mut self) {
+        if let Some(boss) = self.enemies.iter().find(|e| e.health <= 0 && e.kind == Some(EnemyKind::Boss)) {
+            self.defeat_boss(boss.x, boss.y);
+        }
+        let off_limits = self.off_limits();
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        let floor = self.floor();
+        let mut spawned = Vec::new();
+        for slime in self.enemies.iter().filter(|e| e.health <= 0 && e.kind == Some(EnemyKind::Slime)) {
+            let pos = (slime.x, slime.y);
+            let spots = std::iter::once(pos)
+                .chain((0..4).filter_map(|dir| open_step(pos, dir, maze)))
+                .filter(|&(x, y)| (x, y) != (self.player.x, self.player.y) && !off_limits.contains(&(x, y)))
+                .filter(|&(x, y)| !self.enemies.iter().any(|e| e.health > 0 && (e.x, e.y) == (x, y)))
+                .filter(|spot| !spawned.iter().any(|e: &Entity| (e.x, e.y) == *spot))
+                .take(2)
+                .collect::<Vec<_>>();
+            for (x, y) in spots {
+                spawned.push(Entity::enemy(EnemyKind::Slimeling, x, y, floor));
             }
         }
-
-        // Remove dead enemies after all moves/attacks
         self.enemies.retain(|e| e.health > 0);
+        self.enemies.extend(spawned);
     }
 
-    /// Player move, with enemy logic
-    pub fn move_player(&mut self, direction: &str) -> PyResult<bool> {
+    /// Player move, with enemy logic. Refused unless the game is `Playing`.
+    pub fn move_player(&mut self, direction: &str) -> Result<bool> {
+        // Health can be edited from outside, so catch deaths that happened between turns
+        if self.state == GameState::Playing && self.player.health <= 0 {
+            self.state = GameState::Lost;
+        }
+        if self.state != GameState::Playing {
+            return Ok(false);
+        }
+
         // Pre-fetch room dimensions to avoid double borrow
         let up_room_height = if self.current_room_row > 0 {
             self.mazes[self.current_room_row - 1][self.current_room_col].height
@@ -322,8 +353,6 @@ impl Dungeon {
         let left_room_width = if self.current_room_col > 0 {
             self.mazes[self.current_room_row][self.current_room_col - 1].width
         } else { 0 };
-        let maze_width = self.mazes[self.current_room_row][self.current_room_col].width;
-        let maze_height = self.mazes[self.current_room_row][self.current_room_col].height;
 
         // Pre-fetch all needed immutable values before mutable borrow
         let maze_chests = self.mazes[self.current_room_row][self.current_room_col].chests.clone();
@@ -335,7 +364,7 @@ impl Dungeon {
             "right" => (1, 0, 1),
             "down" => (0, 1, 2),
             "left" => (-1, 0, 3),
-            _ => return Err(pyo3::exceptions::PyValueError::new_err("Invalid direction")),
+            _ => return Err(Error::invalid("Invalid direction")),
         };
 
         let new_x = self.player.x as isize + dx;
@@ -353,14 +382,7 @@ impl Dungeon {
             if is_adjacent(self.player.x, self.player.y, chest_col, chest_row, maze) && maze.can_move(self.player.y, self.player.x, dir_idx) {
                 let chest = &mut maze.chests[chest_idx];
                 if let Some(contents) = chest.open() {
-                    // Apply chest contents to player
-                    match contents {
-                        ChestContent::Gold { amount } => self.player.gold += amount as i32,
-                        ChestContent::Sword { .. } => self.player.attack += 1,
-                        ChestContent::Shield { .. } => self.player.armor += 1,
-                        ChestContent::Potion { .. } => self.player.health += 5, 
-                        ChestContent::Key { .. } => {/* handle key logic later i few ides for keys*/},
-                    }
+                    self.player.pick_up(contents);
                 }
                 // Remove chest from maze
                 maze.chests.remove(chest_idx);
@@ -376,7 +398,7 @@ impl Dungeon {
         if let Some(enemy) = self.enemies.iter_mut().find(|e| e.x as isize == new_x && e.y as isize == new_y) {
             enemy.take_damage(self.player.attack);
             if enemy.health <= 0 {
-                self.enemies.retain(|e| e.health > 0);
+                self.remove_dead_enemies();
             }
             self.enemy_attack_player();
             self.move_enemies();
@@ -392,6 +414,14 @@ impl Dungeon {
             if maze.can_move(self.player.y, self.player.x, dir_idx) {
                 self.player.x = new_x as usize;
                 self.player.y = new_y as usize;
+                maze.reveal(self.player.x, self.player.y);
+                if self.exit_unlocked()
+                    && (self.current_room_row, self.current_room_col) == self.exit_room
+                    && (self.player.x, self.player.y) == self.exit_cell
+                {
+                    self.state = GameState::Won;
+                    return Ok(true);
+                }
                 self.enemy_attack_player();
                 self.move_enemies();
                 return Ok(true);
@@ -427,36 +457,381 @@ impl Dungeon {
             _ => return Ok(false),
         };
 
+        // A living boss keeps its room sealed
+        if self.is_sealed() {
+            return Ok(false);
+        }
+
         let maze = &mut self.mazes[self.current_room_row][self.current_room_col];
         if maze.can_move(self.player.y, self.player.x, dir_idx) {
+            if !self.pass_door((next_room_row, next_room_col)) {
+                return Ok(false);
+            }
+            self.leave_room();
             self.current_room_row = next_room_row;
             self.current_room_col = next_room_col;
+            self.visited_rooms[next_room_row][next_room_col] = true;
             self.player.x = next_x;
             self.player.y = next_y;
-            self.spawn_enemies(3);
+            self.mazes[next_room_row][next_room_col].reveal(next_x, next_y);
+            self.enter_room();
             Ok(true)
         } else {
             Ok(false)
         }
     }
 
+    /// Check that the dungeon can be played through: walls agree on both sides, exits
+    /// line up, every cell is reachable, nothing blocks a doorway and the keys add up.
+    /// An empty list means nothing was found.
+    pub fn validate(&self) -> Vec<Diagnostic> {
+        self.diagnostics()
+    }
+
+    /// Build dungeons from `seed`, `seed + 1`, ... until one passes `validate`.
+    /// Fails with the last dungeon's problems if none does within `max_attempts`.
+    #[allow(clippy::too_many_arguments)]
+    pub fn generate_valid(
+        rows: usize,
+        cols: usize,
+        maze_width: usize,
+        maze_height: usize,
+        player: Entity,
+        seed: Option<u64>,
+        algorithms: Option<Vec<Algorithm>>,
+        room_algorithms: Option<HashMap<(usize, usize), Algorithm>>,
+        braid: f64,
+        respawn: Option<Respawn>,
+        max_attempts: usize,
+    ) -> Result<Self> {
+        let seed = seed.unwrap_or_else(rng::random_seed);
+        let mut problems = Vec::new();
+        for attempt in 0..max_attempts as u64 {
+            let dungeon = Self::new(
+                rows,
+                cols,
+                maze_width,
+                maze_height,
+                player.clone(),
+                Some(seed.wrapping_add(attempt)),
+                algorithms.clone(),
+                room_algorithms.clone(),
+                braid,
+                respawn.clone(),
+            )?;
+            problems = dungeon.diagnostics();
+            if problems.is_empty() {
+                return Ok(dungeon);
+            }
+        }
+        let messages: Vec<_> = problems.iter().map(|d| d.message.as_str()).collect();
+        Err(Error::invalid(format!(
+            "No valid dungeon in {} attempts: {}",
+            max_attempts,
+            messages.join("; ")
+        )))
+    }
+
+    /// Draw every room as one SVG document, with the player and enemies in the current room.
+    pub fn to_svg(&self, options: Option<RenderOptions>) -> String {
+        render::dungeon_svg(self, &options.unwrap_or_default())
+    }
+
+    /// Write the whole game state to a file
+    pub fn save(&self, path: &str) -> Result<()> {
+        std::fs::write(path, self.to_bytes()?)?;
+        Ok(())
+    }
+
+    /// Read a game state written by `save`
+    pub fn load(path: &str) -> Result<Self> {
+        Self::from_bytes(&std::fs::read(path)?)
+    }
+
+    /// The whole game state in the save format, as `save` writes it
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        save::encode(self)
+    }
+
+    /// Read a game state written by `to_bytes` or `save`. Fails on saves whose rooms,
+    /// positions or doors don't fit together, rather than panicking later in play.
+    pub fn from_bytes(data: &[u8]) -> Result<Self> {
+        let dungeon: Self = save::decode(data)?;
+        dungeon.check_layout()?;
+        Ok(dungeon)
+    }
+
+    /// Cross-field checks for a decoded save; each room checks its own walls and chests
+    fn check_layout(&self) -> Result<()> {
+        let (rows, cols) = (self.mazes.len(), self.mazes.first().map_or(0, Vec::len));
+        let fits = |lens: Vec<usize>| lens.len() == rows && lens.into_iter().all(|len| len == cols);
+        if cols == 0 || !fits(self.mazes.iter().map(Vec::len).collect()) {
+            return Err(Error::invalid("Corrupt save: rooms don't form a grid"));
+        }
+        if !fits(self.visited_rooms.iter().map(Vec::len).collect()) || !fits(self.rooms.iter().map(Vec::len).collect()) {
+            return Err(Error::invalid("Corrupt save: room state doesn't match the rooms"));
+        }
+        let in_bounds = |(r, c): (usize, usize)| r < rows && c < cols;
+        let here = (self.current_room_row, self.current_room_col);
+        let doors = self.doors.iter().flat_map(|d| [d.from_room, d.to_room]);
+        if !in_bounds(here) || !in_bounds(self.exit_room) || !doors.into_iter().all(in_bounds) {
+            return Err(Error::invalid("Corrupt save: room out of bounds"));
+        }
+        let on_floor = |(r, c): (usize, usize), (x, y): (usize, usize)| x < self.mazes[r][c].width && y < self.mazes[r][c].height;
+        if !on_floor(here, (self.player.x, self.player.y)) || !on_floor(self.exit_room, self.exit_cell) {
+            return Err(Error::invalid("Corrupt save: player or exit outside its room"));
+        }
+        for (r, row) in self.rooms.iter().enumerate() {
+            for (c, room) in row.iter().enumerate() {
+                let enemies = if (r, c) == here { &self.enemies } else { &room.enemies };
+                if enemies.iter().any(|e| !on_floor((r, c), (e.x, e.y))) {
+                    return Err(Error::invalid(format!("Corrupt save: enemy outside room ({}, {})", r, c)));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Use an item from the player's bag. Takes a turn, so adjacent enemies strike back.
+    pub fn use_item(&mut self, item: Item) -> Result<bool> {
+        if self.state != GameState::Playing {
+            return Ok(false);
+        }
+        self.player.use_item(item)?;
+        self.enemy_attack_player();
+        self.move_enemies();
+        Ok(true)
+    }
+
+    pub fn equip(&mut self, item: Item) -> Result<()> {
+        self.player.equip(item)
+    }
+
+    pub fn unequip(&mut self, slot: Slot) -> bool {
+        self.player.unequip(slot)
+    }
+
+    pub fn drop_item(&mut self, item: Item, count: u32) -> Result<()> {
+        self.player.drop_item(item, count)
+    }
+
+    /// Doors on the passages out of the current room
+    pub fn doors_here(&self) -> Vec<Door> {
+        let here = (self.current_room_row, self.current_room_col);
+        self.doors.iter().filter(|d| d.from_room == here || d.to_room == here).cloned().collect()
+    }
+
+    /// Whether the exit can still be reached from here: every room reachable and
+    /// enough keys left over for the exit once every locked door is open
+    pub fn solvable(&self) -> bool {
+        let here = (self.current_room_row, self.current_room_col);
+        door::solve(&self.doors, &self.room_keys(), here, self.player.keys(), self.keys_required)
+    }
+
+    /// Merchant in the current room, if there is one
+    pub fn shop(&self) -> Option<Shop> {
+        let room = (self.current_room_row, self.current_room_col);
+        self.shops.iter().find(|shop| shop.room == room).cloned()
+    }
+
+    /// Buy from the merchant in the current room. Fails without change if the player
+    /// is short of gold or the merchant is out of stock.
+    pub fn buy(&mut self, item: Item, count: u32) -> Result<bool> {
+        if self.state != GameState::Playing {
+            return Ok(false);
+        }
+        let gold = self.player.gold;
+        let shop = self.shop_here()?;
+        let cost = shop::total(shop.price(item)?, count)?;
+        if gold < cost {
+            return Err(Error::invalid(format!("Not enough gold: need {}, have {}", cost, gold)));
+        }
+        shop.take(item, count)?;
+        self.player.gold -= cost;
+        self.player.inventory.add(item, count);
+        Ok(true)
+    }
+
+    /// Sell carried items to the merchant in the current room for half their price.
+    /// Equipped items have to be unequipped first.
+    pub fn sell(&mut self, item: Item, count: u32) -> Result<bool> {
+        if self.state != GameState::Playing {
+            return Ok(false);
+        }
+        let earned = shop::total(self.shop_here()?.sell_price(item)?, count)?;
+        let gold = self.player.gold.checked_add(earned).ok_or_else(|| Error::invalid("Too much gold to carry"))?;
+        self.player.inventory.remove(item, count)?;
+        self.shop_here()?.put(item, count);
+        self.player.gold = gold;
+        Ok(true)
+    }
+
+    /// Roll fresh stock for every merchant. Deterministic for a given seed.
+    pub fn restock(&mut self) {
+        for shop in &mut self.shops {
+            shop.restock(self.seed);
+        }
+    }
+
+    /// Cells of the current room the player can see right now, indexed [row][col]
+    pub fn visible_cells(&self) -> Vec<Vec<bool>> {
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        sight::visible_from(maze, self.player.x, self.player.y)
+    }
+
+    /// What stands on each cell of the current room, row by row, as `Occupant` codes
+    pub fn occupancy(&self) -> Vec<u8> {
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        let mut cells = vec![Occupant::Empty as u8; maze.width * maze.height];
+        for chest in maze.chests.iter().filter(|c| !c.is_open) {
+            cells[chest.row * maze.width + chest.col] = Occupant::Chest as u8;
+        }
+        for enemy in &self.enemies {
+            cells[enemy.y * maze.width + enemy.x] = Occupant::Enemy as u8;
+        }
+        cells[self.player.y * maze.width + self.player.x] = Occupant::Player as u8;
+        cells
+    }
+
+    /// Cells of a room the player has ever seen, indexed [row][col]
+    pub fn explored_cells(&self, row: usize, col: usize) -> Result<Vec<Vec<bool>>> {
+        self.mazes
+            .get(row)
+            .and_then(|r| r.get(col))
+            .map(Maze::explored_cells)
+            .ok_or_else(|| Error::out_of_bounds("Room out of bounds"))
+    }
+
+    /// How much of each room has been seen, from 0.0 to 1.0, indexed [row][col]
+    pub fn exploration(&self) -> Vec<Vec<f64>> {
+        self.mazes.iter().map(|row| row.iter().map(Maze::explored_fraction).collect()).collect()
+    }
+
+    /// Enemies waiting in a room, as they were when the player last left it
+    pub fn room_enemies(&self, row: usize, col: usize) -> Result<Vec<Entity>> {
+        if (row, col) == (self.current_room_row, self.current_room_col) {
+            return Ok(self.enemies.clone());
+        }
+        self.rooms
+            .get(row)
+            .and_then(|r| r.get(col))
+            .map(|room| room.enemies.clone())
+            .ok_or_else(|| Error::out_of_bounds("Room out of bounds"))
+    }
+
+    /// True while a boss is alive in the current room; its exits stay shut until then
+    pub fn is_sealed(&self) -> bool {
+        self.enemies.iter().any(|e| e.kind == Some(EnemyKind::Boss))
+    }
+
+    /// Whether the exit door opens right now: the player holds `keys_required` keys.
+    /// Follows the keys in hand, so spending one on a locked door locks the exit again.
+    pub fn exit_unlocked(&self) -> bool {
+        self.player.keys() >= self.keys_required
+    }
+
+    /// True once the player has walked through the unlocked exit door
+    pub fn is_won(&self) -> bool {
+        self.state == GameState::Won
+    }
+
+    pub fn is_lost(&self) -> bool {
+        self.state == GameState::Lost
+    }
+
+    /// Stop accepting moves until `resume`. Has no effect once the game is over.
+    pub fn pause(&mut self) {
+        if self.state == GameState::Playing {
+            self.state = GameState::Paused;
+        }
+    }
+
+    pub fn resume(&mut self) {
+        if self.state == GameState::Paused {
+            self.state = GameState::Playing;
+        }
+    }
+
+    /// Swap in a hand-made room, e.g. one loaded with `Maze.from_json`
+    pub fn set_room(&mut self, row: usize, col: usize, maze: Maze) -> Result<()> {
+        if row >= self.mazes.len() || col >= self.mazes[row].len() {
+            return Err(Error::out_of_bounds("Room out of bounds"));
+        }
+        self.mazes[row][col] = maze;
+        Ok(())
+    }
+
     /// Get the current maze room
-    pub fn current_maze<'py>(&self, py: Python<'py>) -> PyResult<Py<Maze>> {
-        let maze = self.mazes[self.current_room_row][self.current_room_col].clone();
-        Py::new(py, maze)
+    pub fn current_maze(&self) -> Maze {
+        self.mazes[self.current_room_row][self.current_room_col].clone()
+    }
+
+    /// Park the current room's enemies so they are still there next time
+    fn leave_room(&mut self) {
+        let room = &mut self.rooms[self.current_room_row][self.current_room_col];
+        room.enemies = std::mem::take(&mut self.enemies);
+        room.left_on = Some(self.turn);
+    }
+
+    /// Bring back the room's survivors, or spawn a fresh pack if the respawn policy says so
+    fn enter_room(&mut self) {
+        if self.boss_pending() {
+            self.spawn_boss();
+            return;
+        }
+        // Nobody picks fights in front of the merchant
+        if self.shop().is_some() {
+            self.enemies.clear();
+            return;
+        }
+        let room = &mut self.rooms[self.current_room_row][self.current_room_col];
+        if room.needs_spawn(&self.respawn, self.turn) {
+            room.enemies.clear();
+            self.spawn_enemies(3);
+            return;
+        }
+        self.enemies = std::mem::take(&mut room.enemies);
+
+        // Someone may be standing in the doorway; shuffle them aside if there is space
+        let off_limits = self.off_limits();
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        let door = (self.player.x, self.player.y);
+        if let Some(i) = self.enemies.iter().position(|e| (e.x, e.y) == door) {
+            let free = (0..4)
+                .filter_map(|dir| open_step(door, dir, maze))
+                .find(|&spot| !off_limits.contains(&spot) && !self.enemies.iter().any(|e| (e.x, e.y) == spot));
+            if let Some((x, y)) = free {
+                self.enemies[i].move_to(x, y);
+            }
+        }
     }
 
     /// Enemies attack player if adjacent (not blocked by wall)
     fn enemy_attack_player(&mut self) {
         let maze = &self.mazes[self.current_room_row][self.current_room_col];
         for enemy in &self.enemies {
-            if is_adjacent(enemy.x, enemy.y, self.player.x, self.player.y, maze) {
+            if can_strike(enemy, &self.player, maze) {
                 self.player.take_damage(enemy.attack);
             }
         }
+        if self.player.health <= 0 {
+            self.state = GameState::Lost;
+        }
     }
 }
 
+/// The exit door goes on the cell of the spawn room farthest from where the player
+/// starts, so nobody begins the game standing on it, and never under a chest.
+fn exit_cell_for(maze: &Maze, start: (usize, usize)) -> (usize, usize) {
+    let distances = DistanceMap::new(maze, &[start]);
+    (0..maze.height)
+        .flat_map(|y| (0..maze.width).map(move |x| (x, y)))
+        .filter(|&(x, y)| !maze.chests.iter().any(|c| (c.col, c.row) == (x, y)))
+        .filter_map(|cell| distances.get(cell).map(|d| (d, cell)))
+        .max_by_key(|&(d, _)| d)
+        .map_or((maze.width / 2, maze.height / 2), |(_, cell)| cell)
+}
+
 /// Check if two positions are adjacent and not blocked by wall
 pub fn is_adjacent(x1: usize, y1: usize, x2: usize, y2: usize, maze: &Maze) -> bool {
     let dx = x2 as isize - x1 as isize;
@@ -473,4 +848,169 @@ pub fn is_adjacent(x1: usize, y1: usize, x2: usize, y2: usize, maze: &Maze) -> b
 
 pub fn manhattan(x1: usize, y1: usize, x2: usize, y2: usize) -> usize {
     x1.abs_diff(x2) + y1.abs_diff(y2)
-}
\ No newline at end of file
+}
+
+/// Whether an enemy can hit its target from where it stands, by its kind's attack rule
+pub fn can_strike(enemy: &Entity, target: &Entity, maze: &Maze) -> bool {
+    match enemy.kind.map_or(Attack::Melee, EnemyKind::attack) {
+        Attack::Melee => is_adjacent(enemy.x, enemy.y, target.x, target.y, maze),
+        Attack::Phase => manhattan(enemy.x, enemy.y, target.x, target.y) == 1,
+        Attack::Ranged => {
+            is_adjacent(enemy.x, enemy.y, target.x, target.y, maze)
+                || clear_shot((enemy.x, enemy.y), (target.x, target.y), maze)
+        }
+    }
+}
+
+/// Same row or column, within archer range, and no wall along the way
+fn clear_shot(from: (usize, usize), to: (usize, usize), maze: &Maze) -> bool {
+    let dir = match (to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize) {
+        (0, dy) if dy < 0 => 0,
+        (dx, 0) if dx > 0 => 1,
+        (0, dy) if dy > 0 => 2,
+        (dx, 0) if dx < 0 => 3,
+        _ => return false,
+    };
+    if manhattan(from.0, from.1, to.0, to.1) > ARCHER_RANGE {
+        return false;
+    }
+    let mut pos = from;
+    while pos != to {
+        match open_step(pos, dir, maze) {
+            Some(next) => pos = next,
+            None => return false,
+        }
+    }
+    true
+}
+
+/// One cell straight towards the target along the longer axis, ignoring walls
+fn phase_step(from: (usize, usize), to: (usize, usize)) -> (usize, usize) {
+    let (dx, dy) = (to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize);
+    if dx.abs() >= dy.abs() {
+        ((from.0 as isize + dx.signum()) as usize, from.1)
+    } else {
+        (from.0, (from.1 as isize + dy.signum()) as usize)
+    }
+}
+
+/// Shared test fixture: 3x3 rooms of 7x7 cells, the player in the middle of the spawn room
+#[cfg(test)]
+pub(crate) fn test_dungeon(seed: u64) -> Dungeon {
+    Dungeon::new(3, 3, 7, 7, Entity::new(3, 3, 10, 0, 1, 0, true, 0), Some(seed), None, None, 0.0, None).unwrap()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_seed_same_dungeon() {
+        let (a, b) = (test_dungeon(42), test_dungeon(42));
+        assert_eq!(a.to_bytes().unwrap(), b.to_bytes().unwrap());
+        assert_ne!(a.to_bytes().unwrap(), test_dungeon(43).to_bytes().unwrap());
+    }
+
+    #[test]
+    fn player_does_not_start_on_the_exit() {
+        for seed in 0..20 {
+            let d = test_dungeon(seed);
+            assert_eq!(d.exit_room, (d.current_room_row, d.current_room_col));
+            assert_ne!(d.exit_cell, (d.player.x, d.player.y), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn zero_sizes_are_refused() {
+        let player = || Entity::new(0, 0, 10, 0, 1, 0, true, 0);
+        for (rows, cols, width, height) in [(0, 3, 7, 7), (3, 0, 7, 7), (3, 3, 0, 7), (3, 3, 7, 0)] {
+            let built = Dungeon::new(rows, cols, width, height, player(), Some(1), None, None, 0.0, None);
+            assert!(matches!(built, Err(Error::Invalid(_))));
+        }
+    }
+
+    #[test]
+    fn rooms_use_their_own_algorithm() {
+        let player = || Entity::new(3, 3, 10, 0, 1, 0, true, 0);
+        let chosen = HashMap::from([((0, 0), Algorithm::Kruskal), ((2, 1), Algorithm::Eller)]);
+        let d = Dungeon::new(3, 3, 7, 7, player(), Some(9), Some(vec![Algorithm::Prim]), Some(chosen), 0.0, None).unwrap();
+        for (r, row) in d.mazes.iter().enumerate() {
+            for (c, maze) in row.iter().enumerate() {
+                let expected = match (r, c) {
+                    (0, 0) => Algorithm::Kruskal,
+                    (2, 1) => Algorithm::Eller,
+                    _ => Algorithm::Prim,
+                };
+                assert_eq!(maze.algorithm, expected);
+            }
+        }
+        let outside = HashMap::from([((3, 0), Algorithm::Kruskal)]);
+        assert!(Dungeon::new(3, 3, 7, 7, player(), Some(9), None, Some(outside), 0.0, None).is_err());
+    }
+
+    #[test]
+    fn player_outside_the_room_is_refused() {
+        for (x, y) in [(7, 0), (0, 7), (50, 50)] {
+            let built = Dungeon::new(3, 3, 7, 7, Entity::new(x, y, 10, 0, 1, 0, true, 0), Some(1), None, None, 0.0, None);
+            assert!(matches!(built, Err(Error::Invalid(_))));
+        }
+        let corner = Dungeon::new(3, 3, 7, 7, Entity::new(6, 6, 10, 0, 1, 0, true, 0), Some(1), None, None, 0.0, None);
+        assert!(corner.is_ok());
+    }
+
+    #[test]
+    fn bytes_round_trip() {
+        let mut d = test_dungeon(7);
+        for dir in ["up", "left", "down", "right", "right"] {
+            d.move_player(dir).unwrap();
+        }
+        let bytes = d.to_bytes().unwrap();
+        let loaded = Dungeon::from_bytes(&bytes).unwrap();
+        assert_eq!(loaded.to_bytes().unwrap(), bytes);
+        assert_eq!((loaded.player.x, loaded.player.y), (d.player.x, d.player.y));
+        assert_eq!(loaded.turn, d.turn);
+    }
+
+    #[test]
+    fn enemies_keep_off_chests_and_the_exit() {
+        for seed in 0..20 {
+            let mut d = test_dungeon(seed);
+            d.player.health = 1000;
+            d.spawn_enemies(12);
+            // A ghost too, since it ignores the walls on its way
+            let taken: HashSet<_> = d.enemies.iter().map(|e| (e.x, e.y)).chain(d.off_limits()).collect();
+            let (x, y) = (0..7).flat_map(|y| (0..7).map(move |x| (x, y))).find(|cell| !taken.contains(cell) && *cell != (3, 3)).unwrap();
+            d.enemies.push(Entity::enemy(EnemyKind::Ghost, x, y, 2));
+            for _ in 0..30 {
+                let off_limits = d.off_limits();
+                assert!(d.enemies.iter().all(|e| !off_limits.contains(&(e.x, e.y))), "seed {}", seed);
+                d.move_enemies();
+            }
+        }
+    }
+
+    #[test]
+    fn corrupt_saves_are_refused() {
+        let corrupt: [fn(&mut Dungeon); 9] = [
+            |d| d.mazes.clear(),
+            |d| { d.mazes[1].pop(); },
+            |d| { d.visited_rooms.pop(); },
+            |d| d.rooms[2].push(RoomState::default()),
+            |d| d.current_room_col = 3,
+            |d| d.player.x = 50,
+            |d| d.enemies.push(Entity::enemy(EnemyKind::Bat, 0, 7, 0)),
+            |d| d.rooms[0][0].enemies.push(Entity::enemy(EnemyKind::Bat, 9, 0, 2)),
+            |d| d.exit_cell = (7, 0),
+        ];
+        assert!(Dungeon::from_bytes(&test_dungeon(5).to_bytes().unwrap()).is_ok());
+        for (i, corrupt) in corrupt.iter().enumerate() {
+            let mut d = test_dungeon(5);
+            corrupt(&mut d);
+            let bytes = save::encode(&d).unwrap();
+            assert!(matches!(Dungeon::from_bytes(&bytes), Err(Error::Invalid(_))), "case {}", i);
+        }
+        let mut d = test_dungeon(5);
+        d.doors.push(Door { from_room: (1, 1), to_room: (1, 3), kind: door::DoorKind::Locked });
+        assert!(Dungeon::from_bytes(&save::encode(&d).unwrap()).is_err());
+    }
+}
@@ -0,0 +1,118 @@
+// --- Per-room state kept between visits ---
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::entity::Entity;
+
+/// What happens to a room's enemies once the player has left it.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Respawn {
+    /// Survivors wait where they were; dead enemies stay dead
+    Never { },
+    /// Like `Never`, but a fresh pack spawns once the room has been empty of the player this long
+    AfterTurns { turns: u64 },
+    /// A fresh pack on every entry
+    Always { },
+}
+
+impl Respawn {
+    pub fn never() -> Self {
+        Respawn::Never { }
+    }
+
+    pub fn after_turns(turns: u64) -> Self {
+        Respawn::AfterTurns { turns }
+    }
+
+    pub fn always() -> Self {
+        Respawn::Always { }
+    }
+}
+
+impl Default for Respawn {
+    fn default() -> Self {
+        Respawn::Never { }
+    }
+}
+
+/// What stands on a cell, as stored in `Dungeon::occupancy`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Occupant {
+    Empty = 0,
+    Player = 1,
+    Enemy = 2,
+    /// An unopened chest
+    Chest = 3,
+}
+
+/// What a room looked like when the player last walked out of it.
+/// Chests live on the `Maze` itself, so opened ones stay opened without help.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct RoomState {
+    pub enemies: Vec<Entity>,
+    /// Enemy turn on which the player left; None until the first visit ends
+    pub left_on: Option<u64>,
+}
+
+impl RoomState {
+    /// Whether entering should roll a fresh pack instead of restoring the survivors.
+    pub fn needs_spawn(&self, policy: &Respawn, turn: u64) -> bool {
+        match (policy, self.left_on) {
+            (_, None) => true,
+            (Respawn::Never { }, Some(_)) => false,
+            (Respawn::AfterTurns { turns }, Some(left_on)) => turn - left_on >= *turns,
+            (Respawn::Always { }, Some(_)) => true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::{test_dungeon, Dungeon};
+
+    #[test]
+    fn policies_decide_when_to_respawn() {
+        let fresh = RoomState::default();
+        let left = RoomState { enemies: Vec::new(), left_on: Some(10) };
+        for policy in [Respawn::never(), Respawn::after_turns(5), Respawn::always()] {
+            assert!(fresh.needs_spawn(&policy, 0));
+        }
+        assert!(!left.needs_spawn(&Respawn::never(), 100));
+        assert!(!left.needs_spawn(&Respawn::after_turns(5), 14));
+        assert!(left.needs_spawn(&Respawn::after_turns(5), 15));
+        assert!(left.needs_spawn(&Respawn::always(), 10));
+    }
+
+    /// Step out to the room above and straight back in
+    fn leave_and_return(dungeon: &mut Dungeon) {
+        for room in [(0, 1), (1, 1)] {
+            dungeon.leave_room();
+            (dungeon.current_room_row, dungeon.current_room_col) = room;
+            dungeon.enter_room();
+        }
+    }
+
+    #[test]
+    fn rooms_remember_their_enemies_and_chests() {
+        let mut dungeon = test_dungeon(6);
+        dungeon.shops.clear();
+        dungeon.spawn_enemies(3);
+        dungeon.enemies[0].health = 1;
+        let before: Vec<_> = dungeon.enemies.iter().map(|e| (e.x, e.y, e.health, e.kind)).collect();
+        // Opening a chest takes it off the room
+        let chests = dungeon.mazes[1][1].chests.len();
+        dungeon.mazes[1][1].chests.pop();
+
+        leave_and_return(&mut dungeon);
+        let after: Vec<_> = dungeon.enemies.iter().map(|e| (e.x, e.y, e.health, e.kind)).collect();
+        assert_eq!(after, before);
+        assert_eq!(dungeon.mazes[1][1].chests.len(), chests.saturating_sub(1));
+
+        dungeon.respawn = Respawn::always();
+        leave_and_return(&mut dungeon);
+        assert!(!dungeon.enemies.is_empty() && dungeon.enemies.iter().all(|e| e.health > 1));
+    }
+}
@@ -0,0 +1,263 @@
+// --- Routes across the whole dungeon ---
+//
+// `pathfinding` stops at the walls of one room, so routes are planned in two layers:
+//
+//   rooms   Dijkstra over stops, the cells a walker stands on when it starts or comes in
+//           through an exit. Stepping through an exit costs one move and lands on a stop
+//           next door; the doors, the keys in hand and boss seals decide which exits count.
+//   cells   a distance map from every stop prices the legs between stops and later lays
+//           out the cells along them. A closed chest on the way costs two moves: one to
+//           open it, one to step onto its cell.
+//
+// Doors are taken as they stand now. Every locked door on the way spends one of the keys
+// in hand; keys picked up along the route aren't counted.
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap};
+use crate::error::{Error, Result};
+use crate::maze::chest::ChestContent;
+use crate::pathfinding::{DistanceMap, Pos};
+use super::door::{DoorKind, Room};
+use super::validate::{landing, openings};
+use super::Dungeon;
+
+/// What `Dungeon::route_to` looks for
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Target {
+    /// Any chest not opened yet
+    Chest,
+    /// A chest holding a key
+    Key,
+    /// The exit door, locked or not
+    Exit,
+    /// A cell nobody has seen yet
+    Unexplored,
+}
+
+/// A walk through the dungeon, possibly over several rooms.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core", get_all))]
+#[derive(Clone, Debug)]
+pub struct Route {
+    /// Every cell on the way as (room, (x, y)), start and goal included. A closed chest
+    /// on a step is opened by walking into it, then the same move is made again.
+    pub steps: Vec<(Room, Pos)>,
+    /// Moves it takes, counting one more for every chest opened on the way
+    pub moves: usize,
+}
+
+impl Route {
+    /// Moves it takes
+    pub fn len(&self) -> usize {
+        self.moves
+    }
+
+    /// True when the walker already stands on the goal
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Where it ends, as (room, (x, y))
+    pub fn goal(&self) -> (Room, Pos) {
+        self.steps[self.steps.len() - 1]
+    }
+
+    /// The first move as `move_player` takes it ("up", "right", "down" or "left")
+    pub fn first_move(&self) -> Option<&'static str> {
+        let (&(room, (x, y)), &(next_room, (nx, ny))) = (self.steps.first()?, self.steps.get(1)?);
+        // Crossing into another room: the rooms say which way, the cells wrap around
+        let (from, to) = if room == next_room { ((x, y), (nx, ny)) } else { ((room.1, room.0), (next_room.1, next_room.0)) };
+        let dir = match (to.0 as isize - from.0 as isize, to.1 as isize - from.1 as isize) {
+            (0, -1) => "up",
+            (1, 0) => "right",
+            (0, 1) => "down",
+            _ => "left",
+        };
+        Some(dir)
+    }
+}
+
+/// A room and the cell a walker stands on there
+type Stop = (Room, Pos);
+
+impl Dungeon {
+    /// Shortest route from the player to the nearest `target` anywhere in the dungeon.
+    /// None if every one of them is out of reach.
+    pub fn route_to(&self, target: Target) -> Option<Route> {
+        let start = ((self.current_room_row, self.current_room_col), (self.player.x, self.player.y));
+        self.plan(start, &self.targets(target))
+    }
+
+    /// Shortest route between two cells, each as (room, (x, y)), with the player's keys.
+    pub fn route(&self, from: (Room, Pos), to: (Room, Pos)) -> Result<Option<Route>> {
+        for ((r, c), (x, y)) in [from, to] {
+            let maze = self.mazes.get(r).and_then(|row| row.get(c)).ok_or_else(|| Error::out_of_bounds("Room out of bounds"))?;
+            if x >= maze.width || y >= maze.height {
+                return Err(Error::out_of_bounds("Cell out of bounds"));
+            }
+        }
+        Ok(self.plan(from, &[to]))
+    }
+
+    /// Every cell that counts as `target`, as (room, (x, y))
+    fn targets(&self, target: Target) -> Vec<Stop> {
+        if target == Target::Exit {
+            return vec![(self.exit_room, self.exit_cell)];
+        }
+        let mut found = Vec::new();
+        for (r, row) in self.mazes.iter().enumerate() {
+            for (c, maze) in row.iter().enumerate() {
+                if target == Target::Unexplored {
+                    for (y, seen) in maze.explored_cells().into_iter().enumerate() {
+                        found.extend(seen.into_iter().enumerate().filter(|&(_, seen)| !seen).map(|(x, _)| ((r, c), (x, y))));
+                    }
+                    continue;
+                }
+                let chests = maze.chests.iter().filter(|chest| !chest.is_open);
+                found.extend(
+                    chests
+                        .filter(|chest| target == Target::Chest || matches!(chest.contents, Some(ChestContent::Key { })))
+                        .map(|chest| ((r, c), (chest.col, chest.row))),
+                );
+            }
+        }
+        found
+    }
+
+    /// Closed chests in a room, as (x, y); each takes a move to open before it can be walked over
+    fn closed_chests(&self, (r, c): Room) -> Vec<Pos> {
+        self.mazes[r][c].chests.iter().filter(|chest| !chest.is_open).map(|chest| (chest.col, chest.row)).collect()
+    }
+
+    /// Ways out of `room`: the edge cell, the stop it lands on next door and whether
+    /// the door there takes a key
+    fn crossings(&self, room: Room) -> Vec<(Pos, Stop, bool)> {
+        // A boss keeps its room shut until it falls, including one that wakes up when entered
+        let sealed = if room == (self.current_room_row, self.current_room_col) {
+            self.is_sealed()
+        } else {
+            self.boss_rooms.contains(&room) && !self.defeated_bosses.contains(&room)
+        };
+        if sealed {
+            return Vec::new();
+        }
+        let (rows, cols) = (self.mazes.len(), self.mazes[0].len());
+        let (r, c) = room;
+        let maze = &self.mazes[r][c];
+        let neighbours = [
+            (r > 0).then(|| (r - 1, c)),
+            (c + 1 < cols).then_some((r, c + 1)),
+            (r + 1 < rows).then_some((r + 1, c)),
+            (c > 0).then(|| (r, c - 1)),
+        ];
+        let mut found = Vec::new();
+        for (dir, next) in neighbours.into_iter().enumerate() {
+            let Some(next) = next else { continue };
+            let locked = match self.doors.iter().find(|d| d.joins(room, next)) {
+                Some(door) if door.kind == DoorKind::Locked => true,
+                Some(door) if !door.passable_from(room) => continue,
+                _ => false,
+            };
+            let other = &self.mazes[next.0][next.1];
+            found.extend(openings(maze, dir).into_iter().map(|cell| (cell, (next, landing(other, cell, dir)), locked)));
+        }
+        found
+    }
+
+    /// Dijkstra over stops from `start` until the cheapest of `goals` is settled.
+    fn plan(&self, start: Stop, goals: &[Stop]) -> Option<Route> {
+        let keys = self.player.keys();
+        let mut maps: HashMap<Stop, DistanceMap> = HashMap::new();
+        // Cheapest known cost of each (stop, keys spent), and the stop and exit it came through
+        let mut best = HashMap::from([((start, 0), 0)]);
+        let mut came_from: HashMap<(Stop, u32), ((Stop, u32), Pos)> = HashMap::new();
+        let mut heap = BinaryHeap::from([Reverse((0, start, 0))]);
+        let mut found: Option<(usize, Pos, (Stop, u32))> = None;
+
+        while let Some(Reverse((cost, stop, spent))) = heap.pop() {
+            if found.is_some_and(|(f, ..)| f <= cost) {
+                break;
+            }
+            if best[&(stop, spent)] < cost {
+                continue;
+            }
+            let (room, cell) = stop;
+            let chests = self.closed_chests(room);
+            let map = maps.entry(stop).or_insert_with(|| DistanceMap::weighted(&self.mazes[room.0][room.1], &[cell], &chests));
+
+            for &(_, goal) in goals.iter().filter(|goal| goal.0 == room) {
+                if let Some(d) = map.get(goal) {
+                    // A chest that is the goal only needs opening, not stepping onto
+                    let total = cost + d.saturating_sub(u32::from(chests.contains(&goal))) as usize;
+                    if found.is_none_or(|(f, ..)| total < f) {
+                        found = Some((total, goal, (stop, spent)));
+                    }
+                }
+            }
+            for (exit, next, locked) in self.crossings(room) {
+                let Some(d) = map.get(exit) else { continue };
+                let spent_after = spent + u32::from(locked);
+                if spent_after > keys {
+                    continue;
+                }
+                let total = cost + d as usize + 1;
+                if best.get(&(next, spent_after)).is_none_or(|&b| total < b) {
+                    best.insert((next, spent_after), total);
+                    came_from.insert((next, spent_after), ((stop, spent), exit));
+                    heap.push(Reverse((total, next, spent_after)));
+                }
+            }
+        }
+
+        // Walk the legs back to the start, then lay their cells end to end
+        let (moves, goal, mut leg) = found?;
+        let mut legs = vec![(leg.0, goal)];
+        while let Some(&(prev, exit)) = came_from.get(&leg) {
+            legs.push((prev.0, exit));
+            leg = prev;
+        }
+        let mut steps = Vec::new();
+        for (stop, end) in legs.into_iter().rev() {
+            let room = stop.0;
+            let cells = maps[&stop].path(&self.mazes[room.0][room.1], end).expect("legs are measured on their stop's map");
+            steps.extend(cells.into_iter().rev().map(|cell| (room, cell)));
+        }
+        Some(Route { steps, moves })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_dungeon;
+    use crate::maze::chest::Chest;
+    use crate::pathfinding::open_step;
+
+    #[test]
+    fn keys_behind_closed_chests_are_reached() {
+        let mut dungeon = test_dungeon(4);
+        dungeon.enemies.clear();
+        for maze in dungeon.mazes.iter_mut().flatten() {
+            maze.chests.clear();
+        }
+        // Hide a key in the far corner of the spawn room behind a ring of closed chests
+        let room = (dungeon.current_room_row, dungeon.current_room_col);
+        let maze = &dungeon.mazes[room.0][room.1];
+        let key = (0, 0);
+        let ring: Vec<_> = (0..4).filter_map(|dir| open_step(key, dir, maze)).collect();
+        let maze = &mut dungeon.mazes[room.0][room.1];
+        maze.chests.push(Chest::new(key.1, key.0, Some(ChestContent::Key { })));
+        maze.chests.extend(ring.iter().map(|&(x, y)| Chest::new(y, x, None)));
+
+        let route = dungeon.route_to(Target::Key).expect("opening a chest clears the way");
+        assert_eq!(route.goal(), (room, key));
+        assert_eq!(route.len(), route.steps.len());
+
+        for _ in 0..route.len() {
+            let dir = dungeon.route_to(Target::Key).and_then(|route| route.first_move()).expect("still on the way");
+            dungeon.move_player(dir).unwrap();
+        }
+        assert_eq!(dungeon.player.keys(), 1);
+    }
+}
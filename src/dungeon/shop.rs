@@ -0,0 +1,207 @@
+// --- Merchant rooms ---
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::Rng;
+use rand::seq::SliceRandom;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::entity::inventory::Item;
+use crate::rng::{self, Stream};
+use super::{manhattan, Dungeon};
+
+/// One merchant for roughly this many ordinary rooms
+pub const ROOMS_PER_SHOP: usize = 9;
+
+/// Price on the spawn floor; None for items merchants won't trade.
+fn base_price(item: Item) -> Option<u32> {
+    match item {
+        Item::Potion => Some(15),
+        Item::Sword | Item::Shield => Some(40),
+        Item::Greatsword | Item::Aegis => Some(150),
+        Item::Key => None,
+    }
+}
+
+/// Gold for `count` items at `each`, refusing counts too big to pay for in one go
+pub(super) fn total(each: u32, count: u32) -> Result<i32> {
+    each.checked_mul(count)
+        .and_then(|sum| i32::try_from(sum).ok())
+        .ok_or_else(|| Error::invalid(format!("Can't trade {} at once", count)))
+}
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Shop {
+    /// Room the merchant sits in, as (row, col)
+    pub room: (usize, usize),
+    /// Distance from the spawn room; prices go up with it
+    pub floor: usize,
+    /// Items for sale as (item, count)
+    pub stock: Vec<(Item, u32)>,
+    /// How many times the stock has been rolled, so every restock draws something new
+    restocks: u64,
+}
+
+impl Shop {
+    /// What the merchant asks for one of `item`
+    pub fn price(&self, item: Item) -> Result<u32> {
+        let base = base_price(item).ok_or_else(|| Error::invalid(format!("{:?} can't be traded", item)))?;
+        Ok(base * (4 + self.floor as u32) / 4)
+    }
+
+    /// What the merchant pays for one of `item`: half the asking price
+    pub fn sell_price(&self, item: Item) -> Result<u32> {
+        Ok(self.price(item)? / 2)
+    }
+
+    pub fn count(&self, item: Item) -> u32 {
+        self.stock.iter().find(|(i, _)| *i == item).map_or(0, |&(_, n)| n)
+    }
+}
+
+impl Dungeon {
+    /// Pick merchant rooms among those that are neither the spawn room nor boss rooms.
+    pub(super) fn shops_for(seed: u64, rows: usize, cols: usize, boss_rooms: &[(usize, usize)]) -> Vec<Shop> {
+        let spawn = (rows / 2, cols / 2);
+        let mut candidates: Vec<_> = (0..rows)
+            .flat_map(|r| (0..cols).map(move |c| (r, c)))
+            .filter(|room| *room != spawn && !boss_rooms.contains(room))
+            .collect();
+        candidates.shuffle(&mut rng::stream_rng(seed, Stream::Shop));
+        let count = candidates.len().div_ceil(ROOMS_PER_SHOP);
+        let mut shops: Vec<_> = candidates
+            .into_iter()
+            .take(count)
+            .map(|room| Shop::new(seed, room, manhattan(room.1, room.0, spawn.1, spawn.0)))
+            .collect();
+        shops.sort_by_key(|shop| shop.room);
+        shops
+    }
+
+    /// Merchant in the current room, if there is one.
+    pub(super) fn shop_here(&mut self) -> Result<&mut Shop> {
+        let room = (self.current_room_row, self.current_room_col);
+        self.shops
+            .iter_mut()
+            .find(|shop| shop.room == room)
+            .ok_or_else(|| Error::invalid("There is no merchant in this room"))
+    }
+}
+
+impl Shop {
+    pub fn new(seed: u64, room: (usize, usize), floor: usize) -> Self {
+        let mut shop = Shop { room, floor, stock: Vec::new(), restocks: 0 };
+        shop.restock(seed);
+        shop
+    }
+
+    /// Throw out the old stock and roll a new one from the dungeon seed.
+    pub fn restock(&mut self, seed: u64) {
+        let room_seed = rng::room_seed(seed, self.room.0, self.room.1);
+        let mut rng = rng::stream_rng(room_seed.wrapping_add(self.restocks), Stream::Shop);
+        self.restocks += 1;
+        self.stock = vec![(Item::Potion, rng.gen_range(2..=4))];
+        for item in [Item::Sword, Item::Shield] {
+            if rng.gen_bool(0.5) {
+                self.stock.push((item, 1));
+            }
+        }
+    }
+
+    /// Take items off the shelf. Fails without change if there aren't enough.
+    pub fn take(&mut self, item: Item, count: u32) -> Result<()> {
+        let held = self.count(item);
+        if held < count {
+            return Err(Error::invalid(format!("The merchant has only {} {:?}, need {}", held, item, count)));
+        }
+        if let Some(pos) = self.stock.iter().position(|(i, _)| *i == item) {
+            self.stock[pos].1 -= count;
+            if self.stock[pos].1 == 0 {
+                self.stock.remove(pos);
+            }
+        }
+        Ok(())
+    }
+
+    /// Put items the player sold up for sale.
+    pub fn put(&mut self, item: Item, count: u32) {
+        match self.stock.iter_mut().find(|(i, _)| *i == item) {
+            Some((_, n)) => *n += count,
+            None => self.stock.push((item, count)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::test_dungeon;
+
+    /// The test dungeon with the player standing in its merchant's room
+    fn at_the_merchant(gold: i32) -> Dungeon {
+        let mut dungeon = test_dungeon(8);
+        let room = dungeon.shops[0].room;
+        (dungeon.current_room_row, dungeon.current_room_col) = room;
+        dungeon.enemies.clear();
+        dungeon.player.gold = gold;
+        dungeon
+    }
+
+    #[test]
+    fn buying_trades_gold_for_stock() {
+        let mut dungeon = at_the_merchant(1000);
+        let shop = dungeon.shop().unwrap();
+        let (price, stocked) = (shop.price(Item::Potion).unwrap(), shop.count(Item::Potion));
+        assert!(dungeon.buy(Item::Potion, 2).unwrap());
+        assert_eq!(dungeon.player.gold, 1000 - 2 * price as i32);
+        assert_eq!(dungeon.player.inventory.count(Item::Potion), 2);
+        assert_eq!(dungeon.shop().unwrap().count(Item::Potion), stocked - 2);
+
+        // Out of stock, can't be traded, or too many to count: nothing changes
+        let gold = dungeon.player.gold;
+        assert!(dungeon.buy(Item::Potion, stocked).is_err());
+        assert!(dungeon.buy(Item::Key, 1).is_err());
+        assert!(dungeon.buy(Item::Potion, u32::MAX).is_err());
+        assert_eq!(dungeon.player.gold, gold);
+        assert_eq!(dungeon.shop().unwrap().count(Item::Potion), stocked - 2);
+    }
+
+    #[test]
+    fn buying_needs_the_gold() {
+        let mut dungeon = at_the_merchant(1);
+        assert!(dungeon.buy(Item::Potion, 1).is_err());
+        assert_eq!((dungeon.player.gold, dungeon.player.inventory.count(Item::Potion)), (1, 0));
+    }
+
+    #[test]
+    fn selling_pays_half() {
+        let mut dungeon = at_the_merchant(0);
+        dungeon.player.inventory.add(Item::Sword, 1);
+        let shop = dungeon.shop().unwrap();
+        let stocked = shop.count(Item::Sword);
+        assert_eq!(shop.sell_price(Item::Sword).unwrap(), shop.price(Item::Sword).unwrap() / 2);
+        assert!(dungeon.sell(Item::Sword, 1).unwrap());
+        assert_eq!(dungeon.player.gold, shop.sell_price(Item::Sword).unwrap() as i32);
+        assert_eq!(dungeon.shop().unwrap().count(Item::Sword), stocked + 1);
+        assert!(dungeon.sell(Item::Sword, 1).is_err());
+    }
+
+    #[test]
+    fn no_trading_away_from_the_merchant() {
+        let mut dungeon = test_dungeon(8);
+        dungeon.player.gold = 1000;
+        assert!(dungeon.shop().is_none());
+        assert!(dungeon.buy(Item::Potion, 1).is_err());
+    }
+
+    #[test]
+    fn restocking_is_seeded() {
+        let mut a = Shop::new(5, (0, 1), 1);
+        let mut b = Shop::new(5, (0, 1), 1);
+        assert_eq!(a.stock, b.stock);
+        a.restock(5);
+        b.restock(5);
+        assert_eq!(a.stock, b.stock);
+        assert!(a.price(Item::Potion).unwrap() > Shop::new(5, (1, 1), 0).price(Item::Potion).unwrap());
+    }
+}
@@ -0,0 +1,70 @@
+// --- Game state ---
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Where the game stands. Only `Playing` accepts player input.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GameState {
+    Playing,
+    Won,
+    Lost,
+    Paused,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::route::Target;
+    use crate::dungeon::{test_dungeon, Dungeon};
+    use crate::entity::inventory::Item;
+
+    /// A move that is open from the start cell
+    fn open_move(dungeon: &Dungeon) -> &'static str {
+        let maze = dungeon.current_maze();
+        let dir = (0..4).find(|&dir| maze.can_move(dungeon.player.y, dungeon.player.x, dir)).unwrap();
+        ["up", "right", "down", "left"][dir]
+    }
+
+    #[test]
+    fn paused_games_ignore_input() {
+        let mut dungeon = test_dungeon(1);
+        let dir = open_move(&dungeon);
+        dungeon.player.inventory.add(Item::Potion, 1);
+        dungeon.pause();
+        assert_eq!(dungeon.state, GameState::Paused);
+        assert!(!dungeon.move_player(dir).unwrap());
+        assert!(!dungeon.use_item(Item::Potion).unwrap());
+        assert_eq!((dungeon.player.x, dungeon.player.y, dungeon.turn), (3, 3, 0));
+        dungeon.resume();
+        assert_eq!(dungeon.state, GameState::Playing);
+        assert!(dungeon.move_player(dir).unwrap());
+    }
+
+    #[test]
+    fn death_ends_the_game() {
+        let mut dungeon = test_dungeon(1);
+        let dir = open_move(&dungeon);
+        dungeon.player.health = 0;
+        assert!(!dungeon.move_player(dir).unwrap());
+        assert!(dungeon.is_lost());
+        // Once over, pause and resume don't bring it back
+        dungeon.pause();
+        dungeon.resume();
+        assert_eq!(dungeon.state, GameState::Lost);
+    }
+
+    #[test]
+    fn reaching_the_open_exit_wins() {
+        let mut dungeon = test_dungeon(2);
+        dungeon.enemies.clear();
+        dungeon.player.set_keys(dungeon.keys_required);
+        while !dungeon.is_won() {
+            let route = dungeon.route_to(Target::Exit).expect("the exit is in the spawn room");
+            assert!(dungeon.move_player(route.first_move().unwrap()).unwrap());
+        }
+        assert_eq!(dungeon.state, GameState::Won);
+        assert!(!dungeon.move_player(open_move(&dungeon)).unwrap());
+    }
+}
@@ -0,0 +1,203 @@
+// --- Dungeon validation ---
+//
+// `Dungeon::validate` walks a dungeon the way a player would and reports whatever would
+// get them stuck. Checks, in order:
+//
+//   exits      every opening towards a neighbouring room lines up with an opening there
+//   cells      every cell of every room can be reached from one of its entrances
+//   contents   no chest sits in a doorway, no enemy waits where nobody can reach it
+//   exit door  the exit cell can be reached inside its room
+//   keys       the doors can be opened in some order that still leaves the exit's keys
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use std::fmt;
+use crate::maze::Maze;
+use crate::pathfinding::DistanceMap;
+use super::door::{self, Room};
+use super::Dungeon;
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Problem {
+    /// An opening to a neighbouring room leads into a wall there
+    DisconnectedExit,
+    /// A cell can't be walked to from any entrance of its room
+    UnreachableCell,
+    /// A chest sits where the player steps in from another room
+    ChestInDoorway,
+    /// An enemy stands in a cell nobody can walk to
+    EnemyUnreachable,
+    /// The exit door can't be walked to
+    ExitUnreachable,
+    /// Not every room can be reached with enough keys left for the exit
+    Unsolvable,
+}
+
+/// One thing `validate` found wrong.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core", get_all))]
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub problem: Problem,
+    /// Room the problem is in, as (row, col)
+    pub room: Room,
+    /// Cell inside the room, as (x, y), when the problem has one
+    pub cell: Option<(usize, usize)>,
+    pub message: String,
+}
+
+impl Diagnostic {
+    fn new(problem: Problem, room: Room, cell: Option<(usize, usize)>, message: String) -> Self {
+        Diagnostic { problem, room, cell, message }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Diagnostic({:?}, room={:?}, cell={:?}: {})", self.problem, self.room, self.cell, self.message)
+    }
+}
+
+/// Boundary cells of `maze` that open towards direction `dir`, as (x, y)
+pub(super) fn openings(maze: &Maze, dir: usize) -> Vec<(usize, usize)> {
+    let (w, h) = (maze.width, maze.height);
+    let edge: Vec<(usize, usize)> = match dir {
+        0 => (0..w).map(|x| (x, 0)).collect(),
+        1 => (0..h).map(|y| (w - 1, y)).collect(),
+        2 => (0..w).map(|x| (x, h - 1)).collect(),
+        _ => (0..h).map(|y| (0, y)).collect(),
+    };
+    edge.into_iter().filter(|&(x, y)| maze.can_move(y, x, dir)).collect()
+}
+
+/// Where an opening at (x, y) going `dir` lands in the neighbouring room, mirroring `move_player`
+pub(super) fn landing(to: &Maze, (x, y): (usize, usize), dir: usize) -> (usize, usize) {
+    match dir {
+        0 => (x, to.height - 1),
+        1 => (0, y),
+        2 => (x, 0),
+        _ => (to.width - 1, y),
+    }
+}
+
+impl Dungeon {
+    pub(super) fn diagnostics(&self) -> Vec<Diagnostic> {
+        let mut found = Vec::new();
+        let (rows, cols) = (self.mazes.len(), self.mazes.first().map_or(0, Vec::len));
+        let here = (self.current_room_row, self.current_room_col);
+
+        for r in 0..rows {
+            for c in 0..cols {
+                let maze = &self.mazes[r][c];
+                let room = (r, c);
+
+                // Entrances: openings that line up with an opening in the room next door
+                let mut entrances = Vec::new();
+                let neighbours = [
+                    (r > 0).then(|| (r - 1, c)),
+                    (c + 1 < cols).then_some((r, c + 1)),
+                    (r + 1 < rows).then_some((r + 1, c)),
+                    (c > 0).then(|| (r, c - 1)),
+                ];
+                for (dir, next) in neighbours.into_iter().enumerate() {
+                    let Some(next) = next else { continue };
+                    let other = &self.mazes[next.0][next.1];
+                    for cell in openings(maze, dir) {
+                        let (nx, ny) = landing(other, cell, dir);
+                        if other.can_move(ny, nx, (dir + 2) % 4) {
+                            entrances.push(cell);
+                        } else {
+                            let message = format!("Opening at ({}, {}) runs into a wall in room {:?}", cell.0, cell.1, next);
+                            found.push(Diagnostic::new(Problem::DisconnectedExit, room, Some(cell), message));
+                        }
+                    }
+                }
+
+                let mut starts = entrances.clone();
+                if room == here {
+                    starts.push((self.player.x, self.player.y));
+                }
+                let reached = DistanceMap::new(maze, &starts);
+                for y in 0..maze.height {
+                    for x in 0..maze.width {
+                        if reached.get((x, y)).is_none() {
+                            let message = format!("({}, {}) can't be reached from any entrance", x, y);
+                            found.push(Diagnostic::new(Problem::UnreachableCell, room, Some((x, y)), message));
+                        }
+                    }
+                }
+
+                for chest in maze.chests.iter().filter(|ch| !ch.is_open) {
+                    if entrances.contains(&(chest.col, chest.row)) {
+                        let message = format!("Chest at ({}, {}) blocks a doorway", chest.col, chest.row);
+                        found.push(Diagnostic::new(Problem::ChestInDoorway, room, Some((chest.col, chest.row)), message));
+                    }
+                }
+
+                let enemies = if room == here { &self.enemies } else { &self.rooms[r][c].enemies };
+                for enemy in enemies {
+                    if reached.get((enemy.x, enemy.y)).is_none() {
+                        let message = format!("Enemy at ({}, {}) can't be reached", enemy.x, enemy.y);
+                        found.push(Diagnostic::new(Problem::EnemyUnreachable, room, Some((enemy.x, enemy.y)), message));
+                    }
+                }
+
+                if room == self.exit_room {
+                    let (x, y) = self.exit_cell;
+                    if reached.get((x, y)).is_none() {
+                        let message = format!("Exit door at ({}, {}) can't be reached", x, y);
+                        found.push(Diagnostic::new(Problem::ExitUnreachable, room, Some((x, y)), message));
+                    }
+                }
+            }
+        }
+
+        if !door::solve(&self.doors, &self.room_keys(), here, self.player.keys(), self.keys_required) {
+            let message = "Some rooms can't be reached, or not enough keys are left for the exit".to_string();
+            found.push(Diagnostic::new(Problem::Unsolvable, here, None, message));
+        }
+        found
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use super::super::door::{Door, DoorKind};
+    use super::super::test_dungeon as dungeon;
+
+    fn problems(dungeon: &Dungeon) -> Vec<Problem> {
+        dungeon.validate().into_iter().map(|d| d.problem).collect()
+    }
+
+    #[test]
+    fn generated_dungeons_pass() {
+        for seed in 0..20 {
+            let dungeon = dungeon(seed);
+            assert_eq!(problems(&dungeon), vec![], "seed {}", seed);
+            assert!(dungeon.solvable());
+        }
+    }
+
+    #[test]
+    fn agrees_with_the_solver_on_sealed_spawn() {
+        let mut dungeon = dungeon(1);
+        dungeon.doors = [(0, 1), (1, 0), (1, 2), (2, 1)].into_iter().map(|to_room| Door { from_room: (1, 1), to_room, kind: DoorKind::Sealed }).collect();
+        assert!(!dungeon.solvable());
+        assert_eq!(problems(&dungeon), vec![Problem::Unsolvable]);
+    }
+
+    #[test]
+    fn walled_off_cell_is_reported() {
+        let mut dungeon = dungeon(2);
+        // Wall (3, 3) of the top-left room in on all four sides, from both sides
+        let mut doc: serde_json::Value = serde_json::from_str(&dungeon.mazes[0][0].to_json(false).unwrap()).unwrap();
+        let walls = &mut doc["walls"];
+        walls[3][3] = 15.into();
+        for (row, col, side) in [(2, 3, 4), (3, 4, 8), (4, 3, 1), (3, 2, 2)] {
+            walls[row][col] = (walls[row][col].as_u64().unwrap() | side).into();
+        }
+        dungeon.set_room(0, 0, Maze::from_json(&doc.to_string()).unwrap()).unwrap();
+        let found = dungeon.validate();
+        assert!(found.iter().any(|d| d.problem == Problem::UnreachableCell && d.room == (0, 0) && d.cell == Some((3, 3))));
+    }
+}
@@ -0,0 +1,198 @@
+// --- Inventory code ---
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::maze::chest::ChestContent;
+
+/// Attack granted by an equipped sword
+pub const SWORD_ATTACK: i32 = 1;
+/// Armor granted by an equipped shield
+pub const SHIELD_ARMOR: i32 = 1;
+/// Attack granted by an equipped greatsword (boss drop)
+pub const GREATSWORD_ATTACK: i32 = 3;
+/// Armor granted by an equipped aegis (boss drop)
+pub const AEGIS_ARMOR: i32 = 3;
+/// Health restored by drinking a potion
+pub const POTION_HEAL: i32 = 5;
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Item {
+    Sword,
+    Shield,
+    Potion,
+    Key,
+    Greatsword,
+    Aegis,
+}
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Slot {
+    Weapon,
+    Shield,
+}
+
+impl Item {
+    /// Item found in a chest, if the contents aren't gold.
+    pub fn from_content(content: &ChestContent) -> Option<Item> {
+        match content {
+            ChestContent::Gold { .. } => None,
+            ChestContent::Sword { } => Some(Item::Sword),
+            ChestContent::Shield { } => Some(Item::Shield),
+            ChestContent::Potion { } => Some(Item::Potion),
+            ChestContent::Key { } => Some(Item::Key),
+            ChestContent::Greatsword { } => Some(Item::Greatsword),
+            ChestContent::Aegis { } => Some(Item::Aegis),
+        }
+    }
+
+    /// Equipment slot the item goes into, if it can be equipped.
+    pub fn slot(self) -> Option<Slot> {
+        match self {
+            Item::Sword | Item::Greatsword => Some(Slot::Weapon),
+            Item::Shield | Item::Aegis => Some(Slot::Shield),
+            Item::Potion | Item::Key => None,
+        }
+    }
+
+    /// (attack, armor) bonus while equipped.
+    pub fn bonus(self) -> (i32, i32) {
+        match self {
+            Item::Sword => (SWORD_ATTACK, 0),
+            Item::Shield => (0, SHIELD_ARMOR),
+            Item::Greatsword => (GREATSWORD_ATTACK, 0),
+            Item::Aegis => (0, AEGIS_ARMOR),
+            Item::Potion | Item::Key => (0, 0),
+        }
+    }
+}
+
+/// Carried item stacks plus what is currently equipped.
+/// Equipped items are not counted in the stacks.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Inventory {
+    stacks: Vec<(Item, u32)>,
+    pub weapon: Option<Item>,
+    pub shield: Option<Item>,
+}
+
+impl Inventory {
+    pub fn count(&self, item: Item) -> u32 {
+        self.stacks.iter().find(|(i, _)| *i == item).map_or(0, |&(_, n)| n)
+    }
+
+    /// Carried stacks as (item, count), in the order they were first picked up.
+    pub fn items(&self) -> Vec<(Item, u32)> {
+        self.stacks.clone()
+    }
+
+    pub fn equipped(&self, slot: Slot) -> Option<Item> {
+        match slot {
+            Slot::Weapon => self.weapon,
+            Slot::Shield => self.shield,
+        }
+    }
+
+    /// Number of distinct stacks carried
+    pub fn len(&self) -> usize {
+        self.stacks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stacks.is_empty()
+    }
+}
+
+impl Inventory {
+    pub fn add(&mut self, item: Item, count: u32) {
+        if count == 0 {
+            return;
+        }
+        match self.stacks.iter_mut().find(|(i, _)| *i == item) {
+            Some((_, n)) => *n += count,
+            None => self.stacks.push((item, count)),
+        }
+    }
+
+    /// Take `count` of an item out of the stacks. Fails without change if there aren't enough.
+    pub fn remove(&mut self, item: Item, count: u32) -> Result<()> {
+        let held = self.count(item);
+        if held < count {
+            return Err(Error::invalid(format!("Only {} {:?} in inventory, need {}", held, item, count)));
+        }
+        if let Some(pos) = self.stacks.iter().position(|(i, _)| *i == item) {
+            self.stacks[pos].1 -= count;
+            if self.stacks[pos].1 == 0 {
+                self.stacks.remove(pos);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn set_count(&mut self, item: Item, count: u32) {
+        self.stacks.retain(|(i, _)| *i != item);
+        self.add(item, count);
+    }
+
+    pub fn slot_mut(&mut self, slot: Slot) -> &mut Option<Item> {
+        match slot {
+            Slot::Weapon => &mut self.weapon,
+            Slot::Shield => &mut self.shield,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::entity::Entity;
+
+    #[test]
+    fn stacks_add_and_remove() {
+        let mut bag = Inventory::default();
+        bag.add(Item::Potion, 2);
+        bag.add(Item::Key, 1);
+        bag.add(Item::Potion, 1);
+        bag.add(Item::Sword, 0);
+        assert_eq!(bag.items(), vec![(Item::Potion, 3), (Item::Key, 1)]);
+        // Not enough: nothing changes
+        assert!(bag.remove(Item::Key, 2).is_err());
+        assert_eq!(bag.count(Item::Key), 1);
+        bag.remove(Item::Key, 1).unwrap();
+        assert_eq!(bag.items(), vec![(Item::Potion, 3)]);
+        bag.set_count(Item::Potion, 0);
+        assert!(bag.is_empty());
+    }
+
+    #[test]
+    fn equipment_moves_between_bag_and_slots() {
+        let mut player = Entity::new(0, 0, 10, 0, 1, 0, true, 0);
+        player.inventory.add(Item::Sword, 1);
+        player.equip(Item::Sword).unwrap();
+        assert_eq!((player.attack, player.inventory.count(Item::Sword)), (1 + SWORD_ATTACK, 0));
+        assert!(player.equip(Item::Potion).is_err());
+
+        // A better weapon goes on straight away and the old one back in the bag
+        player.pick_up(ChestContent::Greatsword { });
+        assert_eq!(player.inventory.equipped(Slot::Weapon), Some(Item::Greatsword));
+        assert_eq!((player.attack, player.inventory.count(Item::Sword)), (1 + GREATSWORD_ATTACK, 1));
+
+        assert!(player.unequip(Slot::Weapon));
+        assert!(!player.unequip(Slot::Weapon));
+        assert_eq!((player.attack, player.inventory.count(Item::Greatsword)), (1, 1));
+    }
+
+    #[test]
+    fn potions_heal_and_keys_stay_put() {
+        let mut player = Entity::new(0, 0, 3, 0, 1, 0, true, 1);
+        assert!(player.use_item(Item::Potion).is_err());
+        player.pick_up(ChestContent::Potion { });
+        player.use_item(Item::Potion).unwrap();
+        assert_eq!((player.health, player.inventory.count(Item::Potion)), (3 + POTION_HEAL, 0));
+        assert!(player.use_item(Item::Key).is_err());
+        assert_eq!(player.keys(), 1);
+    }
+}
@@ -0,0 +1,102 @@
+// --- Enemy archetypes ---
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// How far a skeleton archer can shoot along a clear row or column
+pub const ARCHER_RANGE: usize = 4;
+
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum EnemyKind {
+    /// Fragile and erratic: flutters randomly half the time
+    Bat,
+    /// Slow; splits into two slimelings when killed
+    Slime,
+    /// What a slime leaves behind. Doesn't split again
+    Slimeling,
+    /// Shoots along open rows and columns instead of closing in
+    Skeleton,
+    /// Drifts straight through walls, and strikes through them too
+    Ghost,
+    /// Heavily armored, hits hard, moves every other turn
+    Tank,
+    /// Guardian of a boss room. Never spawns at random; see `dungeon::boss`
+    Boss,
+}
+
+/// How an enemy gets around the room.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Movement {
+    /// A* chase through open passages
+    Chase,
+    /// Random step half the time, otherwise chase
+    Erratic,
+    /// Chase, but only on even turns
+    Slow,
+    /// Straight towards the player, ignoring walls
+    Phase,
+}
+
+/// How an enemy reaches the player.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Attack {
+    /// Adjacent, with no wall in between
+    Melee,
+    /// Melee, or a clear straight line up to `ARCHER_RANGE` cells
+    Ranged,
+    /// Adjacent, walls don't matter
+    Phase,
+}
+
+impl EnemyKind {
+    /// (health, armor, attack) on the spawn floor; deeper floors add to these.
+    pub fn base_stats(self) -> (i32, i32, i32) {
+        match self {
+            EnemyKind::Bat => (2, 0, 1),
+            EnemyKind::Slime => (4, 0, 1),
+            EnemyKind::Slimeling => (2, 0, 1),
+            EnemyKind::Skeleton => (3, 0, 1),
+            EnemyKind::Ghost => (3, 0, 1),
+            EnemyKind::Tank => (8, 2, 2),
+            EnemyKind::Boss => (20, 1, 2),
+        }
+    }
+
+    /// Stats scaled by the room's distance from the spawn room.
+    pub fn stats(self, floor: usize) -> (i32, i32, i32) {
+        let (health, armor, attack) = self.base_stats();
+        let floor = floor as i32;
+        (health + floor, armor, attack + floor / 2)
+    }
+
+    pub fn movement(self) -> Movement {
+        match self {
+            EnemyKind::Bat => Movement::Erratic,
+            EnemyKind::Slime | EnemyKind::Tank | EnemyKind::Boss => Movement::Slow,
+            EnemyKind::Slimeling | EnemyKind::Skeleton => Movement::Chase,
+            EnemyKind::Ghost => Movement::Phase,
+        }
+    }
+
+    pub fn attack(self) -> Attack {
+        match self {
+            EnemyKind::Skeleton => Attack::Ranged,
+            EnemyKind::Ghost => Attack::Phase,
+            _ => Attack::Melee,
+        }
+    }
+
+    /// Kinds that can spawn this far from the spawn room.
+    pub fn spawnable(floor: usize) -> Vec<EnemyKind> {
+        let mut kinds = vec![EnemyKind::Bat, EnemyKind::Slime];
+        if floor >= 1 {
+            kinds.push(EnemyKind::Skeleton);
+        }
+        if floor >= 2 {
+            kinds.push(EnemyKind::Ghost);
+            kinds.push(EnemyKind::Tank);
+        }
+        kinds
+    }
+}
@@ -1,28 +1,32 @@
+pub mod inventory;
+pub mod kind;
+
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use crate::maze::chest::ChestContent;
+use inventory::{Inventory, Item, Slot, POTION_HEAL};
+use kind::EnemyKind;
 
 /// Represents an entity in the dungeon (player or enemy).
-#[pyclass]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Entity {
-    #[pyo3(get, set)]
     pub x: usize,
-    #[pyo3(get, set)]
     pub y: usize,
-    #[pyo3(get, set)]
     pub health: i32,
-    #[pyo3(get, set)]
     pub armor: i32,
-    #[pyo3(get, set)]
     pub attack: i32,
-    #[pyo3(get, set)]
     pub gold: i32,
-    #[pyo3(get, set)]
     pub is_player: bool, // true for player, false for enemy
+    pub inventory: Inventory,
+    /// Archetype of an enemy; None for the player
+    pub kind: Option<EnemyKind>,
 }
 
-#[pymethods]
 impl Entity {
-    #[new]
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         x: usize,
         y: usize,
@@ -31,8 +35,77 @@ impl Entity {
         attack: i32,
         gold: i32,
         is_player: bool,
+        keys: u32,
     ) -> Self {
-        Entity { x, y, health, armor, attack, gold, is_player }
+        let mut inventory = Inventory::default();
+        inventory.add(Item::Key, keys);
+        Entity { x, y, health, armor, attack, gold, is_player, inventory, kind: None }
+    }
+
+    /// An enemy of the given kind with its stat block for `floor`.
+    pub fn enemy(kind: EnemyKind, x: usize, y: usize, floor: usize) -> Self {
+        let (health, armor, attack) = kind.stats(floor);
+        Entity {
+            x,
+            y,
+            health,
+            armor,
+            attack,
+            gold: 0,
+            is_player: false,
+            inventory: Inventory::default(),
+            kind: Some(kind),
+        }
+    }
+
+    pub fn keys(&self) -> u32 {
+        self.inventory.count(Item::Key)
+    }
+
+    pub fn set_keys(&mut self, keys: u32) {
+        self.inventory.set_count(Item::Key, keys);
+    }
+
+    /// Put a carried sword or shield into its slot. Whatever was there goes back in the bag.
+    pub fn equip(&mut self, item: Item) -> Result<()> {
+        let slot = item.slot().ok_or_else(|| Error::invalid(format!("{:?} can't be equipped", item)))?;
+        self.inventory.remove(item, 1)?;
+        self.unequip(slot);
+        *self.inventory.slot_mut(slot) = Some(item);
+        let (attack, armor) = item.bonus();
+        self.attack += attack;
+        self.armor += armor;
+        Ok(())
+    }
+
+    /// Move the item in `slot` back into the bag. Returns false if the slot was empty.
+    pub fn unequip(&mut self, slot: Slot) -> bool {
+        let Some(item) = self.inventory.slot_mut(slot).take() else {
+            return false;
+        };
+        let (attack, armor) = item.bonus();
+        self.attack -= attack;
+        self.armor -= armor;
+        self.inventory.add(item, 1);
+        true
+    }
+
+    /// Drink a potion, or equip a sword/shield. Keys are spent by doors, not used directly.
+    pub fn use_item(&mut self, item: Item) -> Result<()> {
+        match item {
+            Item::Potion => {
+                self.inventory.remove(Item::Potion, 1)?;
+                self.health += POTION_HEAL;
+                Ok(())
+            }
+            Item::Sword | Item::Shield | Item::Greatsword | Item::Aegis => self.equip(item),
+            Item::Key => Err(Error::invalid("Keys can't be used on their own")),
+        }
+    }
+
+    /// Throw away carried items. Equipped items have to be unequipped first.
+    pub fn drop_item(&mut self, item: Item, count: u32) -> Result<()> {
+        self.inventory.remove(item, count)
     }
 
     pub fn move_to(&mut self, x: usize, y: usize) {
@@ -45,9 +118,28 @@ impl Entity {
         self.y = y;
     }
 
+    /// Armor soaks up damage, but every real hit gets at least one point through
     pub fn take_damage(&mut self, amount: i32) {
-        let reduced_amount = (amount - self.armor).max(0);
+        let reduced_amount = if amount > 0 { (amount - self.armor).max(1) } else { 0 };
         self.health -= reduced_amount;
     }
+}
 
+impl Entity {
+    /// Take the contents of a chest. Gold goes to the purse, everything else into the
+    /// bag, and equipment is put on straight away if the slot is free or holds something weaker.
+    pub fn pick_up(&mut self, content: ChestContent) {
+        match content {
+            ChestContent::Gold { amount } => self.gold += amount as i32,
+            other => {
+                let item = Item::from_content(&other).expect("only gold has no item");
+                self.inventory.add(item, 1);
+                if let Some(slot) = item.slot()
+                    && self.inventory.equipped(slot).is_none_or(|worn| worn.bonus() < item.bonus())
+                {
+                    self.equip(item).expect("item was just added");
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,373 @@
+// --- Reinforcement learning environment ---
+//
+// A Gym-style wrapper around `Dungeon` for training agents. `reset` starts an episode
+// from a seed and `step` plays one action through `Dungeon::move_player`, scoring it
+// with the weights in `Rewards`. Observations describe the current room as planes of
+// bytes, laid out (channel, row, col) so they can be handed to numpy as they are.
+//
+// `VecDungeonEnv` holds a batch of environments and steps them in parallel with rayon,
+// starting a fresh dungeon whenever one of them finishes.
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rayon::prelude::*;
+use crate::dungeon::{Dungeon, manhattan};
+use crate::entity::Entity;
+use crate::entity::inventory::Item;
+use crate::error::{Error, Result};
+use crate::rng;
+
+/// Move up, right, down or left (the usual 0-3 directions), or 4 to drink a potion
+pub const ACTIONS: usize = 5;
+const DIRECTIONS: [&str; 4] = ["up", "right", "down", "left"];
+const POTION: usize = 4;
+
+/// Observation planes, each `height * width` bytes
+pub const CHANNELS: usize = 6;
+/// Wall bitmask of every cell: 1 = top, 2 = right, 4 = bottom, 8 = left
+pub const WALLS: usize = 0;
+/// 1 where the player stands
+pub const PLAYER: usize = 1;
+/// Health of the enemy in the cell (capped at 255), 0 if there is none
+pub const ENEMIES: usize = 2;
+/// 1 on unopened chests
+pub const CHESTS: usize = 3;
+/// 1 on a locked exit door, 2 once it is open
+pub const EXIT: usize = 4;
+/// 1 on cells the player has seen
+pub const EXPLORED: usize = 5;
+
+/// Weights for each part of the reward. A step's reward is the sum of the ones that apply.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core", get_all, set_all))]
+#[derive(Clone, Debug)]
+pub struct Rewards {
+    /// Per coin picked up
+    pub gold: f32,
+    /// Per enemy killed by the player
+    pub kill: f32,
+    /// Per key picked up
+    pub key: f32,
+    /// Per room of progress towards the exit, once it is unlocked
+    pub exit_progress: f32,
+    pub win: f32,
+    pub death: f32,
+    /// Added to every step, usually a small penalty to keep episodes short
+    pub step: f32,
+}
+
+impl Rewards {
+    pub fn new(gold: f32, kill: f32, key: f32, exit_progress: f32, win: f32, death: f32, step: f32) -> Self {
+        Rewards { gold, kill, key, exit_progress, win, death, step }
+    }
+}
+
+impl Default for Rewards {
+    fn default() -> Self {
+        Rewards::new(0.01, 0.1, 1.0, 0.5, 10.0, -5.0, -0.001)
+    }
+}
+
+/// What happened during a step, besides the reward.
+#[derive(Clone, Debug, Default)]
+pub struct Info {
+    /// Whether the action did anything (false for walking into a wall, or a potion with none left)
+    pub moved: bool,
+    pub gold: i32,
+    pub kills: u32,
+    pub keys: u32,
+    pub health: i32,
+    /// Current room, as (row, col)
+    pub room: (usize, usize),
+    pub won: bool,
+    pub lost: bool,
+    /// The episode hit `max_steps` before it was won or lost
+    pub truncated: bool,
+    /// Steps taken in this episode
+    pub steps: u64,
+    /// Last observation of a finished episode, set by `VecDungeonEnv` before it resets
+    pub final_observation: Option<Vec<u8>>,
+}
+
+/// Result of one `step`, in Gym order.
+#[derive(Clone, Debug)]
+pub struct Step {
+    pub obs: Vec<u8>,
+    pub reward: f32,
+    pub done: bool,
+    pub info: Info,
+}
+
+/// One dungeon played by an agent.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug)]
+pub struct DungeonEnv {
+    pub rows: usize,
+    pub cols: usize,
+    pub room_width: usize,
+    pub room_height: usize,
+    pub rewards: Rewards,
+    /// Episodes end after this many steps; 0 means no limit
+    pub max_steps: u64,
+    /// Player every episode starts with
+    pub player: Entity,
+    dungeon: Option<Dungeon>,
+    steps: u64,
+}
+
+impl DungeonEnv {
+    /// Without a `player`, episodes start in the middle of the middle room with 10 health.
+    pub fn new(
+        rows: usize,
+        cols: usize,
+        room_width: usize,
+        room_height: usize,
+        rewards: Option<Rewards>,
+        max_steps: u64,
+        player: Option<Entity>,
+    ) -> Result<Self> {
+        if rows == 0 || cols == 0 || room_width == 0 || room_height == 0 {
+            return Err(Error::invalid("Rows, cols and room size must be at least 1"));
+        }
+        let player = player.unwrap_or_else(|| Entity::new(room_width / 2, room_height / 2, 10, 0, 1, 0, true, 0));
+        if player.x >= room_width || player.y >= room_height {
+            return Err(Error::out_of_bounds("Player starts outside the room"));
+        }
+        Ok(DungeonEnv { rows, cols, room_width, room_height, rewards: rewards.unwrap_or_default(), max_steps, player, dungeon: None, steps: 0 })
+    }
+
+    /// Observation shape as (channels, height, width)
+    pub fn observation_shape(&self) -> (usize, usize, usize) {
+        (CHANNELS, self.room_height, self.room_width)
+    }
+
+    /// Dungeon of the current episode, if `reset` has been called
+    pub fn dungeon(&self) -> Option<&Dungeon> {
+        self.dungeon.as_ref()
+    }
+
+    /// Start a new episode. The same seed always gives the same dungeon.
+    pub fn reset(&mut self, seed: Option<u64>) -> Result<Vec<u8>> {
+        let seed = seed.unwrap_or_else(rng::random_seed);
+        let dungeon = Dungeon::new(self.rows, self.cols, self.room_width, self.room_height, self.player.clone(), Some(seed), None, None, 0.0, None)?;
+        self.steps = 0;
+        let obs = observe(&dungeon);
+        self.dungeon = Some(dungeon);
+        Ok(obs)
+    }
+
+    /// Play one action. Once an episode is done, further steps change nothing until `reset`:
+    /// they return the last observation with no reward and `done` still set.
+    pub fn step(&mut self, action: usize) -> Result<Step> {
+        let rewards = self.rewards.clone();
+        let dungeon = self.dungeon.as_mut().ok_or_else(|| Error::invalid("Call reset before step"))?;
+        if action >= ACTIONS {
+            return Err(Error::invalid(format!("Action must be below {}, got {}", ACTIONS, action)));
+        }
+
+        // A finished episode stays as it ended: no move, no reward, still done
+        let truncated = self.max_steps > 0 && self.steps >= self.max_steps;
+        if dungeon.is_won() || dungeon.is_lost() || truncated {
+            let info = idle_info(dungeon, self.steps, truncated && !dungeon.is_won() && !dungeon.is_lost());
+            return Ok(Step { obs: observe(dungeon), reward: 0.0, done: true, info });
+        }
+
+        let gold = dungeon.player.gold;
+        let keys = dungeon.player.keys();
+        let distance = exit_distance(dungeon);
+        let kills = u32::from(action < POTION && strike_kills(dungeon, action));
+        let moved = if action == POTION {
+            // No potion to drink is a wasted turn, not an error
+            dungeon.use_item(Item::Potion).unwrap_or(false)
+        } else {
+            dungeon.move_player(DIRECTIONS[action])?
+        };
+        self.steps += 1;
+
+        let won = dungeon.is_won();
+        let lost = dungeon.is_lost();
+        let truncated = !won && !lost && self.max_steps > 0 && self.steps >= self.max_steps;
+        let mut reward = rewards.step
+            + rewards.gold * (dungeon.player.gold - gold) as f32
+            + rewards.kill * kills as f32
+            + rewards.key * dungeon.player.keys().saturating_sub(keys) as f32;
+        if dungeon.exit_unlocked() {
+            reward += rewards.exit_progress * (distance - exit_distance(dungeon));
+        }
+        if won {
+            reward += rewards.win;
+        }
+        if lost {
+            reward += rewards.death;
+        }
+
+        let info = Info { moved, kills, ..idle_info(dungeon, self.steps, truncated) };
+        Ok(Step { obs: observe(dungeon), reward, done: won || lost || truncated, info })
+    }
+}
+
+/// Info for a step that neither moved nor killed anything
+fn idle_info(dungeon: &Dungeon, steps: u64, truncated: bool) -> Info {
+    Info {
+        moved: false,
+        gold: dungeon.player.gold,
+        kills: 0,
+        keys: dungeon.player.keys(),
+        health: dungeon.player.health,
+        room: (dungeon.current_room_row, dungeon.current_room_col),
+        won: dungeon.is_won(),
+        lost: dungeon.is_lost(),
+        truncated,
+        steps,
+        final_observation: None,
+    }
+}
+
+/// Current room of `dungeon` as `CHANNELS` planes of `height * width` bytes.
+pub fn observe(dungeon: &Dungeon) -> Vec<u8> {
+    let here = (dungeon.current_room_row, dungeon.current_room_col);
+    let maze = &dungeon.mazes[here.0][here.1];
+    let plane = maze.width * maze.height;
+    let at = |channel: usize, x: usize, y: usize| channel * plane + y * maze.width + x;
+    let mut obs = vec![0; CHANNELS * plane];
+    obs[WALLS * plane..(WALLS + 1) * plane].copy_from_slice(&maze.wall_masks());
+    for (i, &seen) in maze.explored_cells().iter().flatten().enumerate() {
+        obs[EXPLORED * plane + i] = u8::from(seen);
+    }
+    for chest in maze.chests.iter().filter(|c| !c.is_open) {
+        obs[at(CHESTS, chest.col, chest.row)] = 1;
+    }
+    for enemy in &dungeon.enemies {
+        obs[at(ENEMIES, enemy.x, enemy.y)] = enemy.health.clamp(1, 255) as u8;
+    }
+    if here == dungeon.exit_room {
+        let (x, y) = dungeon.exit_cell;
+        obs[at(EXIT, x, y)] = if dungeon.exit_unlocked() { 2 } else { 1 };
+    }
+    obs[at(PLAYER, dungeon.player.x, dungeon.player.y)] = 1;
+    obs
+}
+
+/// Distance to the exit door counted in rooms, with the exit room itself counting
+/// from 1 at the far corner down to 0 on the door.
+fn exit_distance(dungeon: &Dungeon) -> f32 {
+    let (row, col) = (dungeon.current_room_row, dungeon.current_room_col);
+    let rooms = manhattan(col, row, dungeon.exit_room.1, dungeon.exit_room.0);
+    if rooms > 0 {
+        return (rooms + 1) as f32;
+    }
+    let maze = &dungeon.mazes[row][col];
+    let (x, y) = dungeon.exit_cell;
+    manhattan(dungeon.player.x, dungeon.player.y, x, y) as f32 / (maze.width + maze.height) as f32
+}
+
+/// Whether moving in `action`'s direction hits an enemy hard enough to kill it.
+/// Mirrors `move_player`: a closed chest in the way is opened instead.
+fn strike_kills(dungeon: &Dungeon, action: usize) -> bool {
+    let maze = &dungeon.mazes[dungeon.current_room_row][dungeon.current_room_col];
+    let (dx, dy) = [(0, -1), (1, 0), (0, 1), (-1, 0)][action];
+    let x = dungeon.player.x as isize + dx;
+    let y = dungeon.player.y as isize + dy;
+    if maze.chests.iter().any(|c| !c.is_open && (c.col as isize, c.row as isize) == (x, y)) {
+        return false;
+    }
+    dungeon.enemies.iter().find(|e| (e.x as isize, e.y as isize) == (x, y)).is_some_and(|enemy| {
+        let mut enemy = enemy.clone();
+        enemy.take_damage(dungeon.player.attack);
+        enemy.health <= 0
+    })
+}
+
+/// A batch of environments with the same settings, stepped together.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug)]
+pub struct VecDungeonEnv {
+    envs: Vec<DungeonEnv>,
+    /// Seed each environment uses for its next episode
+    next_seeds: Vec<u64>,
+}
+
+impl VecDungeonEnv {
+    pub fn new(count: usize, env: DungeonEnv) -> Result<Self> {
+        if count == 0 {
+            return Err(Error::invalid("Need at least one environment"));
+        }
+        Ok(VecDungeonEnv { envs: vec![env; count], next_seeds: vec![0; count] })
+    }
+
+    pub fn len(&self) -> usize {
+        self.envs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.envs.is_empty()
+    }
+
+    pub fn envs(&self) -> &[DungeonEnv] {
+        &self.envs
+    }
+
+    /// Reset every environment and return their observations back to back.
+    /// Environment `i` plays seed `seed + i`, then `seed + i + len`, `seed + i + 2 * len`, ...
+    pub fn reset(&mut self, seed: Option<u64>) -> Result<Vec<u8>> {
+        let seed = seed.unwrap_or_else(rng::random_seed);
+        let count = self.envs.len() as u64;
+        for (i, next) in self.next_seeds.iter_mut().enumerate() {
+            *next = seed.wrapping_add(i as u64).wrapping_add(count);
+        }
+        let observations = self
+            .envs
+            .par_iter_mut()
+            .enumerate()
+            .map(|(i, env)| env.reset(Some(seed.wrapping_add(i as u64))))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(observations.concat())
+    }
+
+    /// Step every environment with its action. A finished environment is reset straight
+    /// away: its `obs` is the first of the new episode and the last one of the old
+    /// episode is kept in `info.final_observation`.
+    pub fn step(&mut self, actions: &[usize]) -> Result<Vec<Step>> {
+        if actions.len() != self.envs.len() {
+            return Err(Error::invalid(format!("Expected {} actions, got {}", self.envs.len(), actions.len())));
+        }
+        let count = self.envs.len() as u64;
+        self.envs
+            .par_iter_mut()
+            .zip(self.next_seeds.par_iter_mut())
+            .zip(actions.par_iter())
+            .map(|((env, next_seed), &action)| {
+                let mut step = env.step(action)?;
+                if step.done {
+                    let obs = env.reset(Some(*next_seed))?;
+                    *next_seed = next_seed.wrapping_add(count);
+                    step.info.final_observation = Some(std::mem::replace(&mut step.obs, obs));
+                }
+                Ok(step)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncated_episodes_stay_put() {
+        let mut env = DungeonEnv::new(3, 3, 7, 7, None, 3, None).unwrap();
+        env.reset(Some(1)).unwrap();
+        let mut last = env.step(1).unwrap();
+        while !last.done {
+            last = env.step(1).unwrap();
+        }
+        assert!(last.info.truncated);
+        let player = env.dungeon().unwrap().player.clone();
+        for action in 0..ACTIONS {
+            let step = env.step(action).unwrap();
+            assert!(step.done && step.info.truncated && !step.info.moved);
+            assert_eq!(step.reward, 0.0);
+            assert_eq!(step.obs, last.obs);
+            assert_eq!(step.info.steps, 3);
+        }
+        assert_eq!((env.dungeon().unwrap().player.x, env.dungeon().unwrap().player.y), (player.x, player.y));
+    }
+}
@@ -0,0 +1,50 @@
+// --- Errors ---
+use std::fmt;
+
+/// Everything the crate can fail with. The Python bindings raise the matching
+/// exception: `ValueError`, `IndexError` or `OSError`.
+#[derive(Debug)]
+pub enum Error {
+    /// A bad argument or malformed input: an unknown direction, a corrupt save, broken JSON...
+    Invalid(String),
+    /// A row, column or cell outside the grid
+    OutOfBounds(String),
+    /// Reading or writing a file failed
+    Io(std::io::Error),
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl Error {
+    pub fn invalid(message: impl Into<String>) -> Self {
+        Error::Invalid(message.into())
+    }
+
+    pub fn out_of_bounds(message: impl Into<String>) -> Self {
+        Error::OutOfBounds(message.into())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Invalid(message) | Error::OutOfBounds(message) => f.write_str(message),
+            Error::Io(err) => err.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(err) => Some(err),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::Io(err)
+    }
+}
@@ -1,15 +1,15 @@
-use pyo3::prelude::*;
+// pyo3 0.21's generated wrappers trip this lint under edition 2024
+#![cfg_attr(feature = "python", allow(unsafe_op_in_unsafe_fn))]
 pub mod maze; 
 pub mod dungeon;
 pub mod entity;
-use crate::maze::Maze;
-use crate::dungeon::Dungeon;
-use crate::entity::Entity;
+pub mod env;
+pub mod error;
+pub mod pathfinding;
+pub mod render;
+pub mod rng;
+pub mod save;
+#[cfg(feature = "python")]
+mod python;
 
-#[pymodule]
-fn dungeon_core(_py: Python, m: &PyModule) -> PyResult<()> {
-    m.add_class::<Maze>()?;
-    m.add_class::<Dungeon>()?;
-    m.add_class::<Entity>()?;
-    Ok(())
-}
+pub use crate::error::{Error, Result};
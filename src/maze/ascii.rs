@@ -0,0 +1,236 @@
+// --- ASCII room drawings ---
+//
+// The format `Maze::display` has always printed:
+//
+// +---+---+   +
+// | @     |   |
+// +   +---+   +
+// |   | K   E |
+// +---+---+---+
+//
+// Every cell is 4 columns wide and 2 lines tall. `---` / `|` are walls, blanks are
+// passages. Optional glyphs sit in the middle of a cell:
+//
+//   @    player             E    enemy
+//   S    sword chest        D    shield chest
+//   P    potion chest       K    key chest
+//   G    greatsword chest   A    aegis chest
+//   C    closed empty chest c    opened chest
+//   12   gold chest (the amount, right aligned, up to 999)
+//
+// Trailing spaces may be stripped by editors, so short lines are padded back out.
+use super::Maze;
+use super::cell::Direction;
+use super::chest::{Chest, ChestContent};
+use crate::entity::Entity;
+use crate::error::{Error, Result};
+
+/// Entity position as (x, y), matching `Entity`.
+pub type Position = (usize, usize);
+
+/// A room parsed from text, with the entity markers it contained.
+pub struct AsciiRoom {
+    pub maze: Maze,
+    pub player: Option<Position>,
+    pub enemies: Vec<Position>,
+}
+
+fn chest_glyph(chest: &Chest) -> String {
+    let glyph = match (&chest.contents, chest.is_open) {
+        (_, true) => "c".to_string(),
+        (None, false) => "C".to_string(),
+        (Some(ChestContent::Gold { amount }), false) => return format!("{:>3}", (*amount).min(999)),
+        (Some(ChestContent::Sword { }), false) => "S".to_string(),
+        (Some(ChestContent::Shield { }), false) => "D".to_string(),
+        (Some(ChestContent::Potion { }), false) => "P".to_string(),
+        (Some(ChestContent::Key { }), false) => "K".to_string(),
+        (Some(ChestContent::Greatsword { }), false) => "G".to_string(),
+        (Some(ChestContent::Aegis { }), false) => "A".to_string(),
+    };
+    format!(" {} ", glyph)
+}
+
+pub fn render(maze: &Maze, chests: bool, player: Option<&Entity>, enemies: &[Entity]) -> String {
+    let mut out = String::new();
+    let glyph_at = |row: usize, col: usize| -> String {
+        if player.is_some_and(|p| p.x == col && p.y == row) {
+            return " @ ".to_string();
+        }
+        if enemies.iter().any(|e| e.x == col && e.y == row) {
+            return " E ".to_string();
+        }
+        if chests && let Some(chest) = maze.chests.iter().find(|c| c.row == row && c.col == col) {
+            return chest_glyph(chest);
+        }
+        "   ".to_string()
+    };
+
+    for row in 0..maze.height {
+        for col in 0..maze.width {
+            out.push_str(if maze.grid.has_wall(row, col, Direction::Top) { "+---" } else { "+   " });
+        }
+        out.push_str("+\n");
+
+        for col in 0..maze.width {
+            out.push(if maze.grid.has_wall(row, col, Direction::Left) { '|' } else { ' ' });
+            out.push_str(&glyph_at(row, col));
+            if col == maze.width - 1 {
+                out.push(if maze.grid.has_wall(row, col, Direction::Right) { '|' } else { ' ' });
+            }
+        }
+        out.push('\n');
+    }
+
+    for col in 0..maze.width {
+        out.push_str(if maze.grid.has_wall(maze.height - 1, col, Direction::Bottom) { "+---" } else { "+   " });
+    }
+    out.push_str("+\n");
+    out
+}
+
+fn parse_glyph(glyph: &str, row: usize, col: usize, room: &mut AsciiRoom) -> Result<()> {
+    let contents = match glyph.trim() {
+        "" => return Ok(()),
+        "@" => {
+            room.player = Some((col, row));
+            return Ok(());
+        }
+        "E" => {
+            room.enemies.push((col, row));
+            return Ok(());
+        }
+        "c" => {
+            let mut chest = Chest::new(row, col, None);
+            chest.is_open = true;
+            room.maze.chests.push(chest);
+            return Ok(());
+        }
+        "C" => None,
+        "S" => Some(ChestContent::Sword { }),
+        "D" => Some(ChestContent::Shield { }),
+        "P" => Some(ChestContent::Potion { }),
+        "K" => Some(ChestContent::Key { }),
+        "G" => Some(ChestContent::Greatsword { }),
+        "A" => Some(ChestContent::Aegis { }),
+        digits => match digits.parse::<u32>() {
+            Ok(amount) => Some(ChestContent::Gold { amount }),
+            Err(_) => return Err(Error::invalid(format!("Unknown glyph '{}' in cell ({}, {})", digits, row, col))),
+        },
+    };
+    room.maze.chests.push(Chest::new(row, col, contents));
+    Ok(())
+}
+
+pub fn parse(text: &str) -> Result<AsciiRoom> {
+    let lines: Vec<&str> = text.trim_end_matches(['\n', '\r', ' ']).lines().collect();
+    let first = lines.first().copied().unwrap_or("");
+    let line_len = first.trim_end().len();
+    if !first.starts_with('+') || line_len < 5 || (line_len - 1) % 4 != 0 {
+        return Err(Error::invalid("First line must be a wall line like +---+---+"));
+    }
+    if lines.len() < 3 || lines.len().is_multiple_of(2) {
+        return Err(Error::invalid("Expected alternating wall and cell lines, starting and ending with a wall line"));
+    }
+    let width = (line_len - 1) / 4;
+    let height = lines.len() / 2;
+
+    let padded: Vec<Vec<char>> = lines
+        .iter()
+        .enumerate()
+        .map(|(i, line)| {
+            let chars: Vec<char> = line.trim_end_matches('\r').chars().collect();
+            if chars.len() > line_len {
+                return Err(Error::invalid(format!("Line {} is longer than {} characters", i + 1, line_len)));
+            }
+            let mut chars = chars;
+            chars.resize(line_len, ' ');
+            Ok(chars)
+        })
+        .collect::<Result<_>>()?;
+
+    let mut room = AsciiRoom { maze: Maze::new(width, height), player: None, enemies: Vec::new() };
+
+    // Horizontal walls: line 2 * k sits above row k
+    for k in 0..=height {
+        let line = &padded[2 * k];
+        for col in 0..width {
+            if line[4 * col] != '+' {
+                return Err(Error::invalid(format!("Expected '+' at line {}, column {}", 2 * k + 1, 4 * col + 1)));
+            }
+            let segment: String = line[4 * col + 1..4 * col + 4].iter().collect();
+            let wall = match segment.as_str() {
+                "---" => true,
+                "   " => false,
+                other => return Err(Error::invalid(format!("Bad wall '{}' at line {}, column {}", other, 2 * k + 1, 4 * col + 2))),
+            };
+            if k < height {
+                set_wall(&mut room.maze, k, col, Direction::Top, wall);
+            } else {
+                set_wall(&mut room.maze, k - 1, col, Direction::Bottom, wall);
+            }
+        }
+        if line[4 * width] != '+' {
+            return Err(Error::invalid(format!("Expected '+' at line {}, column {}", 2 * k + 1, 4 * width + 1)));
+        }
+    }
+
+    // Vertical walls and glyphs: line 2 * row + 1
+    for row in 0..height {
+        let line = &padded[2 * row + 1];
+        for col in 0..=width {
+            let wall = match line[4 * col] {
+                '|' => true,
+                ' ' => false,
+                other => return Err(Error::invalid(format!("Bad wall '{}' at line {}, column {}", other, 2 * row + 2, 4 * col + 1))),
+            };
+            if col < width {
+                set_wall(&mut room.maze, row, col, Direction::Left, wall);
+                let glyph: String = line[4 * col + 1..4 * col + 4].iter().collect();
+                parse_glyph(&glyph, row, col, &mut room)?;
+            } else {
+                set_wall(&mut room.maze, row, col - 1, Direction::Right, wall);
+            }
+        }
+    }
+
+    Ok(room)
+}
+
+fn set_wall(maze: &mut Maze, row: usize, col: usize, dir: Direction, wall: bool) {
+    if wall {
+        maze.build_wall(row, col, dir);
+    } else {
+        maze.carve_passage(row, col, dir);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let mut maze = Maze::new(6, 4);
+        maze.generate_maze(Some(vec!["right".into(), "bottom".into()]), Some(3), None, 0.2).unwrap();
+        maze.place_key(3, &[]);
+        maze.chests.push(Chest::new(2, 1, Some(ChestContent::gold(75))));
+        maze.chests.push(Chest::new(0, 5, Some(ChestContent::Potion { })));
+        let player = Entity::new(4, 3, 10, 0, 1, 0, true, 0);
+        let enemy = Entity::new(0, 0, 10, 0, 1, 0, false, 0);
+
+        let text = render(&maze, true, Some(&player), std::slice::from_ref(&enemy));
+        let room = parse(&text).unwrap();
+        assert_eq!(room.player, Some((4, 3)));
+        assert_eq!(room.enemies, vec![(0, 0)]);
+        assert_eq!(room.maze.wall_masks(), maze.wall_masks());
+        assert_eq!(render(&room.maze, true, Some(&player), &[enemy]), text);
+    }
+
+    #[test]
+    fn plain_drawing_round_trips() {
+        let mut maze = Maze::new(5, 5);
+        maze.generate_maze(None, Some(11), None, 0.0).unwrap();
+        let text = render(&maze, false, None, &[]);
+        assert_eq!(render(&parse(&text).unwrap().maze, false, None, &[]), text);
+    }
+}
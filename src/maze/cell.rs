@@ -1,4 +1,3 @@
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum Direction {
     Top = 0,
@@ -7,31 +6,15 @@ pub enum Direction {
     Left = 3,
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
-pub struct Cell {
-    pub walls: [bool; 4], // [Top, Right, Bottom, Left]
-    pub visited: bool,
-}
-
-
+impl Direction {
+    pub const ALL: [Direction; 4] = [Direction::Top, Direction::Right, Direction::Bottom, Direction::Left];
 
-impl Cell {
-    pub fn new() -> Self {
-        Self {
-            walls: [true; 4],
-            visited: false,
+    pub fn opposite(self) -> Direction {
+        match self {
+            Direction::Top => Direction::Bottom,
+            Direction::Right => Direction::Left,
+            Direction::Bottom => Direction::Top,
+            Direction::Left => Direction::Right,
         }
     }
-
-    pub fn has_wall(&self, dir: Direction) -> bool {
-        self.walls[dir as usize]
-    }
-
-    pub fn remove_wall(&mut self, dir: Direction) {
-        self.walls[dir as usize] = false;
-    }
-
-    pub fn set_visited(&mut self) {
-        self.visited = true;
-    }
 }
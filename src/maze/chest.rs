@@ -1,61 +1,61 @@
 // --- Chest code ---
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
+use serde::{Deserialize, Serialize};
 
-#[pyclass]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub enum ChestContent {
-    #[pyo3(name = "Gold")]
     Gold { amount: u32 },
-    #[pyo3(name = "Sword")]
     Sword { },
-    #[pyo3(name = "Shield")]
     Shield { },
-    #[pyo3(name = "Potion")]
     Potion { },
-    #[pyo3(name = "Key")]
     Key { },
+    /// Boss-only loot, never rolled for ordinary chests
+    Greatsword { },
+    Aegis { },
 }
 
-#[pymethods]
 impl ChestContent {
-    #[staticmethod]
     pub fn gold(amount: u32) -> Self {
         ChestContent::Gold { amount }
     }
-    #[staticmethod]
+
     pub fn sword() -> Self {
         ChestContent::Sword { }
     }
-    #[staticmethod]
+
     pub fn shield() -> Self {
         ChestContent::Shield { }
     }
-    #[staticmethod]
+
     pub fn potion() -> Self {
         ChestContent::Potion { }
     }
-    #[staticmethod]
+
     pub fn key() -> Self {
         ChestContent::Key { }
     }
+
+    pub fn greatsword() -> Self {
+        ChestContent::Greatsword { }
+    }
+
+    pub fn aegis() -> Self {
+        ChestContent::Aegis { }
+    }
 }
 
-#[pyclass]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core", get_all))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct Chest {
-    #[pyo3(get)]
     pub row: usize,
-    #[pyo3(get)]
     pub col: usize,
-    #[pyo3(get)]
     pub is_open: bool,
-    #[pyo3(get)]
     pub contents: Option<ChestContent>,
 }
 
-#[pymethods]
 impl Chest {
-    #[new]
     pub fn new(row: usize, col: usize, contents: Option<ChestContent>) -> Self {
         Chest {
             row,
@@ -0,0 +1,466 @@
+// --- Maze generation algorithms ---
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+use serde::{Deserialize, Serialize};
+use super::Maze;
+use crate::error::{Error, Result};
+use super::cell::Direction;
+
+/// Carves passages into a maze whose cells all start fully walled.
+pub trait MazeGenerator {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore);
+}
+
+/// Algorithm used to carve a room. Each one leaves a different mix of
+/// corridor lengths, branching and dead ends.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Algorithm {
+    RecursiveBacktracker,
+    Prim,
+    Kruskal,
+    Wilson,
+    Eller,
+    HuntAndKill,
+    Sidewinder,
+    RecursiveDivision,
+}
+
+impl Algorithm {
+    pub const ALL: [Algorithm; 8] = [
+        Algorithm::RecursiveBacktracker,
+        Algorithm::Prim,
+        Algorithm::Kruskal,
+        Algorithm::Wilson,
+        Algorithm::Eller,
+        Algorithm::HuntAndKill,
+        Algorithm::Sidewinder,
+        Algorithm::RecursiveDivision,
+    ];
+
+    /// Snake-case name, as accepted by `parse`
+    pub fn name(self) -> &'static str {
+        match self {
+            Algorithm::RecursiveBacktracker => "recursive_backtracker",
+            Algorithm::Prim => "prim",
+            Algorithm::Kruskal => "kruskal",
+            Algorithm::Wilson => "wilson",
+            Algorithm::Eller => "eller",
+            Algorithm::HuntAndKill => "hunt_and_kill",
+            Algorithm::Sidewinder => "sidewinder",
+            Algorithm::RecursiveDivision => "recursive_division",
+        }
+    }
+
+    /// Look an algorithm up by its `name`; dashes work as well as underscores.
+    pub fn parse(name: &str) -> Result<Algorithm> {
+        let wanted = name.trim().to_lowercase().replace('-', "_");
+        Algorithm::ALL
+            .into_iter()
+            .find(|a| a.name() == wanted)
+            .ok_or_else(|| Error::invalid(format!("Unknown algorithm: {}", name)))
+    }
+
+    pub fn generator(self) -> Box<dyn MazeGenerator> {
+        match self {
+            Algorithm::RecursiveBacktracker => Box::new(RecursiveBacktracker),
+            Algorithm::Prim => Box::new(Prim),
+            Algorithm::Kruskal => Box::new(Kruskal),
+            Algorithm::Wilson => Box::new(Wilson),
+            Algorithm::Eller => Box::new(Eller),
+            Algorithm::HuntAndKill => Box::new(HuntAndKill),
+            Algorithm::Sidewinder => Box::new(Sidewinder),
+            Algorithm::RecursiveDivision => Box::new(RecursiveDivision),
+        }
+    }
+}
+
+fn random_cell(maze: &Maze, rng: &mut dyn RngCore) -> (usize, usize) {
+    (rng.gen_range(0..maze.height), rng.gen_range(0..maze.width))
+}
+
+/// Depth-first search from the top-left cell. Long, twisty corridors with few branches.
+pub struct RecursiveBacktracker;
+
+impl MazeGenerator for RecursiveBacktracker {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        let mut stack = Vec::new();
+        let mut visited = vec![vec![false; maze.width]; maze.height];
+
+        stack.push((0, 0));
+        visited[0][0] = true;
+
+        while let Some((row, col)) = stack.pop() {
+            let neighbors: Vec<_> = Direction::ALL
+                .iter()
+                .filter_map(|&dir| maze.neighbor(row, col, dir).map(|(r, c)| (r, c, dir)))
+                .filter(|&(r, c, _)| !visited[r][c])
+                .collect();
+
+            if let Some(&(nrow, ncol, dir)) = neighbors.choose(rng) {
+                stack.push((row, col));
+                maze.carve_passage(row, col, dir);
+                visited[nrow][ncol] = true;
+                stack.push((nrow, ncol));
+            }
+        }
+    }
+}
+
+/// Randomized Prim's: grows outward from a random cell. Short corridors, many dead ends.
+pub struct Prim;
+
+impl MazeGenerator for Prim {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        let mut in_maze = vec![vec![false; maze.width]; maze.height];
+        let mut in_frontier = vec![vec![false; maze.width]; maze.height];
+        let mut frontier = Vec::new();
+
+        let (row, col) = random_cell(maze, rng);
+        in_maze[row][col] = true;
+        for dir in Direction::ALL {
+            if let Some((r, c)) = maze.neighbor(row, col, dir) {
+                in_frontier[r][c] = true;
+                frontier.push((r, c));
+            }
+        }
+
+        while !frontier.is_empty() {
+            let (row, col) = frontier.swap_remove(rng.gen_range(0..frontier.len()));
+            let linked: Vec<_> = Direction::ALL
+                .iter()
+                .copied()
+                .filter(|&dir| maze.neighbor(row, col, dir).is_some_and(|(r, c)| in_maze[r][c]))
+                .collect();
+            if let Some(&dir) = linked.choose(rng) {
+                maze.carve_passage(row, col, dir);
+            }
+            in_maze[row][col] = true;
+
+            for dir in Direction::ALL {
+                if let Some((r, c)) = maze.neighbor(row, col, dir)
+                    && !in_maze[r][c]
+                    && !in_frontier[r][c]
+                {
+                    in_frontier[r][c] = true;
+                    frontier.push((r, c));
+                }
+            }
+        }
+    }
+}
+
+/// Randomized Kruskal's: joins random wall pairs between disjoint regions.
+pub struct Kruskal;
+
+impl MazeGenerator for Kruskal {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        let mut edges = Vec::new();
+        for row in 0..maze.height {
+            for col in 0..maze.width {
+                if col + 1 < maze.width {
+                    edges.push((row, col, Direction::Right));
+                }
+                if row + 1 < maze.height {
+                    edges.push((row, col, Direction::Bottom));
+                }
+            }
+        }
+        edges.shuffle(rng);
+
+        let mut sets = DisjointSet::new(maze.width * maze.height);
+        for (row, col, dir) in edges {
+            let (nrow, ncol) = maze.neighbor(row, col, dir).expect("edge stays inside the maze");
+            if sets.union(row * maze.width + col, nrow * maze.width + ncol) {
+                maze.carve_passage(row, col, dir);
+            }
+        }
+    }
+}
+
+struct DisjointSet {
+    parent: Vec<usize>,
+}
+
+impl DisjointSet {
+    fn new(size: usize) -> Self {
+        DisjointSet { parent: (0..size).collect() }
+    }
+
+    fn find(&mut self, mut x: usize) -> usize {
+        while self.parent[x] != x {
+            self.parent[x] = self.parent[self.parent[x]];
+            x = self.parent[x];
+        }
+        x
+    }
+
+    /// Merge the sets holding `a` and `b`. Returns false if they were already joined.
+    fn union(&mut self, a: usize, b: usize) -> bool {
+        let (ra, rb) = (self.find(a), self.find(b));
+        if ra == rb {
+            return false;
+        }
+        self.parent[rb] = ra;
+        true
+    }
+}
+
+/// Wilson's: loop-erased random walks. Produces an unbiased sample of all perfect mazes.
+pub struct Wilson;
+
+impl MazeGenerator for Wilson {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        let mut in_maze = vec![vec![false; maze.width]; maze.height];
+        // Direction last taken out of each cell during the current walk
+        let mut exit_dir = vec![vec![Direction::Top; maze.width]; maze.height];
+
+        let mut order: Vec<(usize, usize)> = (0..maze.height)
+            .flat_map(|r| (0..maze.width).map(move |c| (r, c)))
+            .collect();
+        order.shuffle(rng);
+
+        let (row, col) = order[0];
+        in_maze[row][col] = true;
+
+        for &(start_row, start_col) in &order[1..] {
+            if in_maze[start_row][start_col] {
+                continue;
+            }
+
+            // Walk until we hit the maze, overwriting exits so loops erase themselves
+            let (mut row, mut col) = (start_row, start_col);
+            while !in_maze[row][col] {
+                let options: Vec<_> = Direction::ALL
+                    .iter()
+                    .filter_map(|&dir| maze.neighbor(row, col, dir).map(|pos| (pos, dir)))
+                    .collect();
+                let &((nrow, ncol), dir) = options.choose(rng).expect("maze has more than one cell");
+                exit_dir[row][col] = dir;
+                (row, col) = (nrow, ncol);
+            }
+
+            // Retrace the loop-erased path and carve it
+            let (mut row, mut col) = (start_row, start_col);
+            while !in_maze[row][col] {
+                let dir = exit_dir[row][col];
+                in_maze[row][col] = true;
+                maze.carve_passage(row, col, dir);
+                (row, col) = maze.neighbor(row, col, dir).expect("walk stays inside the maze");
+            }
+        }
+    }
+}
+
+/// Eller's: builds the maze one row at a time, tracking which cells share a set.
+pub struct Eller;
+
+impl MazeGenerator for Eller {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        let mut sets: Vec<Option<usize>> = vec![None; maze.width];
+        let mut next_set = 0;
+
+        for row in 0..maze.height {
+            let last_row = row + 1 == maze.height;
+
+            for set in sets.iter_mut().filter(|s| s.is_none()) {
+                *set = Some(next_set);
+                next_set += 1;
+            }
+
+            // Join neighbours in different sets; the last row must join all of them
+            for col in 0..maze.width.saturating_sub(1) {
+                let (left, right) = (sets[col], sets[col + 1]);
+                if left != right && (last_row || rng.gen_bool(0.5)) {
+                    maze.carve_passage(row, col, Direction::Right);
+                    for set in sets.iter_mut().filter(|s| **s == right) {
+                        *set = left;
+                    }
+                }
+            }
+
+            if last_row {
+                break;
+            }
+
+            // Every set needs at least one passage down
+            let mut below = vec![None; maze.width];
+            let mut groups: Vec<(usize, Vec<usize>)> = Vec::new();
+            for (col, set) in sets.iter().enumerate() {
+                let set = set.expect("every cell in the row has a set");
+                match groups.iter_mut().find(|(s, _)| *s == set) {
+                    Some((_, cols)) => cols.push(col),
+                    None => groups.push((set, vec![col])),
+                }
+            }
+            for (set, cols) in groups {
+                let forced = *cols.choose(rng).expect("set is never empty");
+                for col in cols {
+                    if col == forced || rng.gen_bool(0.3) {
+                        maze.carve_passage(row, col, Direction::Bottom);
+                        below[col] = Some(set);
+                    }
+                }
+            }
+            sets = below;
+        }
+    }
+}
+
+/// Hunt-and-kill: random walks, then scans for an unvisited cell next to the maze when stuck.
+pub struct HuntAndKill;
+
+impl MazeGenerator for HuntAndKill {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        let mut visited = vec![vec![false; maze.width]; maze.height];
+        let mut current = Some(random_cell(maze, rng));
+
+        while let Some((row, col)) = current {
+            visited[row][col] = true;
+
+            let unvisited: Vec<_> = Direction::ALL
+                .iter()
+                .copied()
+                .filter(|&dir| maze.neighbor(row, col, dir).is_some_and(|(r, c)| !visited[r][c]))
+                .collect();
+            if let Some(&dir) = unvisited.choose(rng) {
+                maze.carve_passage(row, col, dir);
+                current = maze.neighbor(row, col, dir);
+                continue;
+            }
+
+            // Hunt: first unvisited cell that touches the visited region
+            current = None;
+            'hunt: for r in 0..maze.height {
+                for c in 0..maze.width {
+                    if visited[r][c] {
+                        continue;
+                    }
+                    let linked: Vec<_> = Direction::ALL
+                        .iter()
+                        .copied()
+                        .filter(|&dir| maze.neighbor(r, c, dir).is_some_and(|(nr, nc)| visited[nr][nc]))
+                        .collect();
+                    if let Some(&dir) = linked.choose(rng) {
+                        maze.carve_passage(r, c, dir);
+                        current = Some((r, c));
+                        break 'hunt;
+                    }
+                }
+            }
+        }
+    }
+}
+
+/// Sidewinder: runs of eastward passages, each closed by one passage north.
+/// Leaves an open corridor along the top row.
+pub struct Sidewinder;
+
+impl MazeGenerator for Sidewinder {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        for row in 0..maze.height {
+            let mut run_start = 0;
+            for col in 0..maze.width {
+                let at_east_edge = col + 1 == maze.width;
+                if row == 0 {
+                    if !at_east_edge {
+                        maze.carve_passage(row, col, Direction::Right);
+                    }
+                    continue;
+                }
+                if at_east_edge || rng.gen_bool(0.5) {
+                    let up = rng.gen_range(run_start..=col);
+                    maze.carve_passage(row, up, Direction::Top);
+                    run_start = col + 1;
+                } else {
+                    maze.carve_passage(row, col, Direction::Right);
+                }
+            }
+        }
+    }
+}
+
+/// Recursive division: starts from an open room and adds walls, each with one gap.
+/// Long straight walls and a boxy, room-like feel.
+pub struct RecursiveDivision;
+
+impl MazeGenerator for RecursiveDivision {
+    fn carve(&self, maze: &mut Maze, rng: &mut dyn RngCore) {
+        for row in 0..maze.height {
+            for col in 0..maze.width {
+                if col + 1 < maze.width {
+                    maze.carve_passage(row, col, Direction::Right);
+                }
+                if row + 1 < maze.height {
+                    maze.carve_passage(row, col, Direction::Bottom);
+                }
+            }
+        }
+
+        // (top row, left col, height, width) of regions still to divide
+        let mut regions = vec![(0, 0, maze.height, maze.width)];
+        while let Some((top, left, height, width)) = regions.pop() {
+            if height < 2 || width < 2 {
+                continue;
+            }
+            let horizontal = match height.cmp(&width) {
+                std::cmp::Ordering::Greater => true,
+                std::cmp::Ordering::Less => false,
+                std::cmp::Ordering::Equal => rng.gen_bool(0.5),
+            };
+
+            if horizontal {
+                let split = rng.gen_range(0..height - 1);
+                let gap = rng.gen_range(0..width);
+                for col in (left..left + width).filter(|&c| c != left + gap) {
+                    maze.build_wall(top + split, col, Direction::Bottom);
+                }
+                regions.push((top, left, split + 1, width));
+                regions.push((top + split + 1, left, height - split - 1, width));
+            } else {
+                let split = rng.gen_range(0..width - 1);
+                let gap = rng.gen_range(0..height);
+                for row in (top..top + height).filter(|&r| r != top + gap) {
+                    maze.build_wall(row, left + split, Direction::Right);
+                }
+                regions.push((top, left, height, split + 1));
+                regions.push((top, left + split + 1, height, width - split - 1));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pathfinding::DistanceMap;
+    use crate::rng::{self, Stream};
+
+    /// Open walls between neighbouring cells, each counted once
+    fn passages(maze: &Maze) -> usize {
+        (0..maze.height)
+            .flat_map(|row| (0..maze.width).map(move |col| (row, col)))
+            .map(|(row, col)| {
+                usize::from(col + 1 < maze.width && maze.can_move(row, col, 1))
+                    + usize::from(row + 1 < maze.height && maze.can_move(row, col, 2))
+            })
+            .sum()
+    }
+
+    #[test]
+    fn every_algorithm_carves_a_perfect_maze() {
+        for algorithm in Algorithm::ALL {
+            for (width, height, seed) in [(1, 1, 0), (1, 6, 1), (5, 2, 2), (7, 7, 3), (12, 9, 4)] {
+                let mut maze = Maze::new(width, height);
+                algorithm.generator().carve(&mut maze, &mut rng::stream_rng(seed, Stream::Layout));
+                // Connected with exactly one passage fewer than cells: a spanning tree
+                let reached = DistanceMap::new(&maze, &[(0, 0)]);
+                let all: Vec<_> = (0..height).flat_map(|y| (0..width).map(move |x| (x, y))).collect();
+                assert!(all.iter().all(|&cell| reached.get(cell).is_some()), "{:?} {}x{}", algorithm, width, height);
+                assert_eq!(passages(&maze), width * height - 1, "{:?} {}x{}", algorithm, width, height);
+            }
+        }
+    }
+}
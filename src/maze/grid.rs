@@ -0,0 +1,168 @@
+// --- Wall storage ---
+//
+// One flat bitset per room. Every wall segment is stored once: `height + 1` rows of
+// `width` horizontal segments, then `height` rows of `width + 1` vertical ones, then a
+// visited bit per cell. A cell reads its four sides from the segments around it, so
+// the two sides of an inner wall can't disagree.
+use serde::{Deserialize, Serialize};
+use super::cell::Direction;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "RawGrid")]
+pub struct Grid {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+/// A grid as read from a save, before its bits are checked against its size
+#[derive(Deserialize)]
+struct RawGrid {
+    width: usize,
+    height: usize,
+    bits: Vec<u64>,
+}
+
+impl TryFrom<RawGrid> for Grid {
+    type Error = String;
+
+    fn try_from(raw: RawGrid) -> Result<Self, String> {
+        let (w, h) = (raw.width, raw.height);
+        // Same count as `visited_start` plus the visited bits, without overflowing on bad input
+        let len = (h + 1)
+            .checked_mul(w)
+            .zip(w.checked_add(1).and_then(|w1| w1.checked_mul(h)))
+            .and_then(|(horizontal, vertical)| horizontal.checked_add(vertical))
+            .and_then(|segments| w.checked_mul(h).and_then(|cells| segments.checked_add(cells)));
+        match len {
+            Some(len) if raw.bits.len() == len.div_ceil(64) => Ok(Grid { width: w, height: h, bits: raw.bits }),
+            _ => Err(format!("Wall grid of {} words doesn't fit a {}x{} room", raw.bits.len(), w, h)),
+        }
+    }
+}
+
+impl Grid {
+    /// Every wall standing, nothing visited.
+    pub fn new(width: usize, height: usize) -> Self {
+        let mut grid = Grid { width, height, bits: Vec::new() };
+        let len = grid.visited_start() + width * height;
+        grid.bits = vec![0; len.div_ceil(64)];
+        for i in 0..grid.visited_start() {
+            grid.set(i, true);
+        }
+        grid
+    }
+
+    /// (width, height) in cells
+    pub fn size(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+
+    fn visited_start(&self) -> usize {
+        (self.height + 1) * self.width + self.height * (self.width + 1)
+    }
+
+    /// Bit of the wall segment on side `dir` of (row, col)
+    fn segment(&self, row: usize, col: usize, dir: Direction) -> usize {
+        debug_assert!(row < self.height && col < self.width);
+        let vertical = (self.height + 1) * self.width;
+        match dir {
+            Direction::Top => row * self.width + col,
+            Direction::Bottom => (row + 1) * self.width + col,
+            Direction::Left => vertical + row * (self.width + 1) + col,
+            Direction::Right => vertical + row * (self.width + 1) + col + 1,
+        }
+    }
+
+    fn get(&self, i: usize) -> bool {
+        self.bits[i / 64] & (1 << (i % 64)) != 0
+    }
+
+    fn set(&mut self, i: usize, on: bool) {
+        if on {
+            self.bits[i / 64] |= 1 << (i % 64);
+        } else {
+            self.bits[i / 64] &= !(1 << (i % 64));
+        }
+    }
+
+    pub fn has_wall(&self, row: usize, col: usize, dir: Direction) -> bool {
+        self.get(self.segment(row, col, dir))
+    }
+
+    /// Raise or knock down one side of a cell; the neighbour across it sees the same wall.
+    pub fn set_wall(&mut self, row: usize, col: usize, dir: Direction, wall: bool) {
+        self.set(self.segment(row, col, dir), wall);
+    }
+
+    /// Walls packed as bits: 1 = top, 2 = right, 4 = bottom, 8 = left.
+    pub fn wall_mask(&self, row: usize, col: usize) -> u8 {
+        Direction::ALL
+            .iter()
+            .filter(|&&dir| self.has_wall(row, col, dir))
+            .fold(0, |mask, &dir| mask | (1 << dir as u8))
+    }
+
+    pub fn is_visited(&self, row: usize, col: usize) -> bool {
+        self.get(self.visited_start() + row * self.width + col)
+    }
+
+    pub fn set_visited(&mut self, row: usize, col: usize) {
+        self.set(self.visited_start() + row * self.width + col, true);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_chacha::ChaCha8Rng;
+
+    /// The old layout: four walls per cell, both sides of an inner wall updated together
+    struct Cells {
+        width: usize,
+        height: usize,
+        walls: Vec<[bool; 4]>,
+    }
+
+    impl Cells {
+        fn set_wall(&mut self, row: usize, col: usize, dir: Direction, wall: bool) {
+            self.walls[row * self.width + col][dir as usize] = wall;
+            let next = match dir {
+                Direction::Top => row.checked_sub(1).map(|r| (r, col)),
+                Direction::Right => (col + 1 < self.width).then_some((row, col + 1)),
+                Direction::Bottom => (row + 1 < self.height).then_some((row + 1, col)),
+                Direction::Left => col.checked_sub(1).map(|c| (row, c)),
+            };
+            if let Some((r, c)) = next {
+                self.walls[r * self.width + c][dir.opposite() as usize] = wall;
+            }
+        }
+    }
+
+    #[test]
+    fn matches_per_cell_walls() {
+        let mut rng = ChaCha8Rng::seed_from_u64(5);
+        for (width, height) in [(1, 1), (1, 7), (9, 1), (13, 11), (64, 3)] {
+            let mut grid = Grid::new(width, height);
+            let mut cells = Cells { width, height, walls: vec![[true; 4]; width * height] };
+            for _ in 0..width * height * 4 {
+                let (row, col) = (rng.gen_range(0..height), rng.gen_range(0..width));
+                let dir = Direction::ALL[rng.gen_range(0..4)];
+                let wall = rng.gen_bool(0.5);
+                grid.set_wall(row, col, dir, wall);
+                cells.set_wall(row, col, dir, wall);
+                if rng.gen_bool(0.3) {
+                    grid.set_visited(row, col);
+                }
+            }
+            for row in 0..height {
+                for col in 0..width {
+                    for dir in Direction::ALL {
+                        assert_eq!(grid.has_wall(row, col, dir), cells.walls[row * width + col][dir as usize]);
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,191 @@
+// --- JSON room layouts ---
+//
+// Schema (version 1):
+//
+// {
+//   "version": 1,
+//   "width": 11,                 // cells per row
+//   "height": 11,                // rows
+//   "walls": [[9, 5, 3, ...], ...],
+//                                // `height` rows of `width` wall bitmasks:
+//                                // 1 = top, 2 = right, 4 = bottom, 8 = left
+//   "chests": [
+//     { "row": 3, "col": 0, "is_open": false, "contents": { "type": "gold", "amount": 40 } },
+//     { "row": 7, "col": 9, "is_open": false, "contents": { "type": "key" } }
+//   ]
+// }
+//
+// Chest `contents` types: "gold" (with "amount"), "sword", "shield", "potion", "key",
+// and the boss drops "greatsword" and "aegis";
+// `null` for an empty chest. Open outer walls are allowed (they are the room's exits),
+// but every inner wall must agree with the neighbouring cell's.
+use serde::{Deserialize, Serialize};
+use super::Maze;
+use super::cell::Direction;
+use super::chest::{Chest, ChestContent};
+use crate::error::{Error, Result};
+
+pub const JSON_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct MazeJson {
+    version: u32,
+    width: usize,
+    height: usize,
+    walls: Vec<Vec<u8>>,
+    #[serde(default)]
+    chests: Vec<ChestJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ChestJson {
+    row: usize,
+    col: usize,
+    #[serde(default)]
+    is_open: bool,
+    contents: Option<ContentJson>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ContentJson {
+    Gold { amount: u32 },
+    Sword,
+    Shield,
+    Potion,
+    Key,
+    Greatsword,
+    Aegis,
+}
+
+impl From<&ChestContent> for ContentJson {
+    fn from(content: &ChestContent) -> Self {
+        match content {
+            ChestContent::Gold { amount } => ContentJson::Gold { amount: *amount },
+            ChestContent::Sword { } => ContentJson::Sword,
+            ChestContent::Shield { } => ContentJson::Shield,
+            ChestContent::Potion { } => ContentJson::Potion,
+            ChestContent::Key { } => ContentJson::Key,
+            ChestContent::Greatsword { } => ContentJson::Greatsword,
+            ChestContent::Aegis { } => ContentJson::Aegis,
+        }
+    }
+}
+
+impl From<ContentJson> for ChestContent {
+    fn from(content: ContentJson) -> Self {
+        match content {
+            ContentJson::Gold { amount } => ChestContent::Gold { amount },
+            ContentJson::Sword => ChestContent::Sword { },
+            ContentJson::Shield => ChestContent::Shield { },
+            ContentJson::Potion => ChestContent::Potion { },
+            ContentJson::Key => ChestContent::Key { },
+            ContentJson::Greatsword => ChestContent::Greatsword { },
+            ContentJson::Aegis => ChestContent::Aegis { },
+        }
+    }
+}
+
+/// The document `to_json` writes, for embedding in a larger one.
+pub fn document(maze: &Maze) -> impl Serialize + use<> {
+    MazeJson {
+        version: JSON_VERSION,
+        width: maze.width,
+        height: maze.height,
+        walls: (0..maze.height).map(|row| (0..maze.width).map(|col| maze.grid.wall_mask(row, col)).collect()).collect(),
+        chests: maze
+            .chests
+            .iter()
+            .map(|c| ChestJson { row: c.row, col: c.col, is_open: c.is_open, contents: c.contents.as_ref().map(ContentJson::from) })
+            .collect(),
+    }
+}
+
+pub fn to_json(maze: &Maze, pretty: bool) -> Result<String> {
+    let doc = document(maze);
+    let result = if pretty { serde_json::to_string_pretty(&doc) } else { serde_json::to_string(&doc) };
+    result.map_err(|e| Error::invalid(format!("Failed to encode maze: {}", e)))
+}
+
+pub fn from_json(data: &str) -> Result<Maze> {
+    let doc: MazeJson = serde_json::from_str(data)
+        .map_err(|e| Error::invalid(format!("Invalid maze JSON: {}", e)))?;
+
+    if doc.version != JSON_VERSION {
+        return Err(Error::invalid(format!("Unsupported maze JSON version {}", doc.version)));
+    }
+    if doc.width == 0 || doc.height == 0 {
+        return Err(Error::invalid("Maze must be at least 1x1"));
+    }
+    if doc.walls.len() != doc.height || doc.walls.iter().any(|row| row.len() != doc.width) {
+        return Err(Error::invalid(format!("Wall grid must be {} rows of {} cells", doc.height, doc.width)));
+    }
+    if let Some((row, col)) = doc.walls.iter().enumerate().find_map(|(r, cells)| cells.iter().position(|&m| m > 0xF).map(|c| (r, c))) {
+        return Err(Error::invalid(format!("Invalid wall bitmask at ({}, {})", row, col)));
+    }
+
+    let mut maze = Maze::new(doc.width, doc.height);
+    let has_wall = |row: usize, col: usize, dir: Direction| doc.walls[row][col] & (1 << dir as u8) != 0;
+
+    // Every inner wall has to be seen the same way from both sides
+    for row in 0..maze.height {
+        for col in 0..maze.width {
+            for dir in [Direction::Right, Direction::Bottom] {
+                if let Some((nrow, ncol)) = maze.neighbor(row, col, dir)
+                    && has_wall(row, col, dir) != has_wall(nrow, ncol, dir.opposite())
+                {
+                    return Err(Error::invalid(format!(
+                        "Asymmetric wall between ({}, {}) and ({}, {})", row, col, nrow, ncol
+                    )));
+                }
+            }
+            for dir in Direction::ALL {
+                maze.grid.set_wall(row, col, dir, has_wall(row, col, dir));
+            }
+        }
+    }
+
+    for chest in doc.chests {
+        if chest.row >= maze.height || chest.col >= maze.width {
+            return Err(Error::invalid(format!("Chest at ({}, {}) is outside the maze", chest.row, chest.col)));
+        }
+        if maze.chests.iter().any(|c| c.row == chest.row && c.col == chest.col) {
+            return Err(Error::invalid(format!("Two chests at ({}, {})", chest.row, chest.col)));
+        }
+        let mut placed = Chest::new(chest.row, chest.col, chest.contents.map(ChestContent::from));
+        placed.is_open = chest.is_open;
+        maze.chests.push(placed);
+    }
+
+    Ok(maze)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn room() -> Maze {
+        let mut maze = Maze::new(7, 5);
+        maze.generate_maze(Some(vec!["left".into(), "top".into()]), Some(9), None, 0.3).unwrap();
+        maze.place_key(9, &[]);
+        maze.chests.push(Chest::new(1, 2, Some(ChestContent::gold(40))));
+        maze
+    }
+
+    #[test]
+    fn round_trip() {
+        let maze = room();
+        let text = to_json(&maze, true).unwrap();
+        let back = from_json(&text).unwrap();
+        assert_eq!(back.wall_masks(), maze.wall_masks());
+        assert_eq!(to_json(&back, true).unwrap(), text);
+    }
+
+    #[test]
+    fn asymmetric_walls_are_rejected() {
+        // 13 leaves the right side of (0, 0) open; its neighbour's left side must be open too
+        let doc = |right: u8| format!(r#"{{"version": 1, "width": 2, "height": 1, "walls": [[13, {}]], "chests": []}}"#, right);
+        assert!(from_json(&doc(7)).is_ok());
+        assert!(matches!(from_json(&doc(15)), Err(Error::Invalid(message)) if message.contains("Asymmetric")));
+    }
+}
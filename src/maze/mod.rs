@@ -1,147 +1,301 @@
+mod ascii;
 mod cell;
+mod grid;
 pub mod chest;
+pub mod generator;
+pub mod json;
+pub mod sight;
+#[cfg(feature = "python")]
 use pyo3::prelude::*;
-use cell::{Cell, Direction};
+use serde::{Deserialize, Serialize};
+use crate::error::{Error, Result};
+use cell::Direction;
+use grid::Grid;
 use chest::{Chest, ChestContent};
+use generator::Algorithm;
+use crate::entity::Entity;
+use crate::pathfinding::{self, DistanceMap};
+use crate::render::{self, Occupants, RenderOptions};
+use crate::rng::{self, Stream};
 use rand::seq::SliceRandom;
-use rand::thread_rng;
-use rand::Rng;
+use rand::{Rng, RngCore};
 
 
 // --- Maze code ---
-#[pyclass]
-#[derive(Clone, Debug)]
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core"))]
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(try_from = "RawMaze")]
 pub struct Maze {
     pub width: usize,
     pub height: usize,
-    pub grid: Vec<Vec<Cell>>,
-    #[pyo3(get)]
+    grid: Grid,
     pub chests: Vec<Chest>,
+    pub algorithm: Algorithm,
 }
-#[pymethods]
+
+/// A maze as read from a save, before its grid and chests are checked against its size
+#[derive(Deserialize)]
+struct RawMaze {
+    width: usize,
+    height: usize,
+    grid: Grid,
+    chests: Vec<Chest>,
+    algorithm: Algorithm,
+}
+
+impl TryFrom<RawMaze> for Maze {
+    type Error = String;
+
+    fn try_from(raw: RawMaze) -> std::result::Result<Self, String> {
+        if raw.grid.size() != (raw.width, raw.height) {
+            return Err(format!("Wall grid doesn't match a {}x{} room", raw.width, raw.height));
+        }
+        if let Some(chest) = raw.chests.iter().find(|c| c.row >= raw.height || c.col >= raw.width) {
+            return Err(format!("Chest at ({}, {}) is outside the room", chest.row, chest.col));
+        }
+        Ok(Maze { width: raw.width, height: raw.height, grid: raw.grid, chests: raw.chests, algorithm: raw.algorithm })
+    }
+}
+
 impl Maze {
-    #[new]
     pub fn new(width: usize, height: usize) -> Self {
-        let grid = vec![vec![Cell::new(); width]; height];
-        Maze { width, height, grid, chests: Vec::new() }
+        Maze { width, height, grid: Grid::new(width, height), chests: Vec::new(), algorithm: Algorithm::RecursiveBacktracker }
     }
 
     pub fn greet(&self) {
         println!("Hello from Rust Maze!");
     }
 
-    pub fn get_cell_walls(&self, row: usize, col: usize) -> PyResult<(bool, bool, bool, bool)> {
+    pub fn get_cell_walls(&self, row: usize, col: usize) -> Result<(bool, bool, bool, bool)> {
         if row >= self.height || col >= self.width {
-            return Err(pyo3::exceptions::PyIndexError::new_err("Cell out of bounds"));
+            return Err(Error::out_of_bounds("Cell out of bounds"));
         }
-        let cell = &self.grid[row][col];
-        Ok((cell.walls[0], cell.walls[1], cell.walls[2], cell.walls[3]))
+        let wall = |dir| self.grid.has_wall(row, col, dir);
+        Ok((wall(Direction::Top), wall(Direction::Right), wall(Direction::Bottom), wall(Direction::Left)))
     }
 
-    pub fn set_cell_visited(&mut self, row: usize, col: usize) -> PyResult<()> {
+    pub fn set_cell_visited(&mut self, row: usize, col: usize) -> Result<()> {
         if row >= self.height || col >= self.width {
-            return Err(pyo3::exceptions::PyIndexError::new_err("Cell out of bounds"));
+            return Err(Error::out_of_bounds("Cell out of bounds"));
         }
-        self.grid[row][col].set_visited();
+        self.grid.set_visited(row, col);
         Ok(())
     }
 
-    pub fn remove_wall(&mut self, row: usize, col: usize, dir: usize) -> PyResult<()> {
+    /// Cells in line of sight of (x, y), indexed [row][col]
+    pub fn visible_cells(&self, x: usize, y: usize) -> Result<Vec<Vec<bool>>> {
+        if y >= self.height || x >= self.width {
+            return Err(Error::out_of_bounds("Cell out of bounds"));
+        }
+        Ok(sight::visible_from(self, x, y))
+    }
+
+    /// Shortest walk from (x, y) `from` to `to`, both ends included; None if walls cut them off
+    pub fn shortest_path(&self, from: (usize, usize), to: (usize, usize)) -> Result<Option<Vec<(usize, usize)>>> {
+        if !self.contains(from) || !self.contains(to) {
+            return Err(Error::out_of_bounds("Cell out of bounds"));
+        }
+        Ok(pathfinding::shortest_path(self, from, to))
+    }
+
+    /// Steps from (x, y) `from` to every cell, indexed [row][col]; None where it can't reach
+    pub fn distance_map(&self, from: (usize, usize)) -> Result<Vec<Vec<Option<u32>>>> {
+        if !self.contains(from) {
+            return Err(Error::out_of_bounds("Cell out of bounds"));
+        }
+        Ok(DistanceMap::new(self, &[from]).rows())
+    }
+
+    fn contains(&self, (x, y): (usize, usize)) -> bool {
+        x < self.width && y < self.height
+    }
+
+    /// Cells that have been seen (or marked visited), indexed [row][col]
+    pub fn explored_cells(&self) -> Vec<Vec<bool>> {
+        (0..self.height).map(|row| (0..self.width).map(|col| self.grid.is_visited(row, col)).collect()).collect()
+    }
+
+    /// Share of the room's cells that have been seen, from 0.0 to 1.0
+    pub fn explored_fraction(&self) -> f64 {
+        let seen = self.cells().filter(|&(row, col)| self.grid.is_visited(row, col)).count();
+        seen as f64 / (self.width * self.height).max(1) as f64
+    }
+
+    /// Wall bitmask of every cell, row by row (1 = top, 2 = right, 4 = bottom, 8 = left)
+    pub fn wall_masks(&self) -> Vec<u8> {
+        self.cells().map(|(row, col)| self.grid.wall_mask(row, col)).collect()
+    }
+
+    /// Knock down one side of a cell. Walls are shared, so the neighbour opens up too.
+    pub fn remove_wall(&mut self, row: usize, col: usize, dir: usize) -> Result<()> {
         if row >= self.height || col >= self.width {
-            return Err(pyo3::exceptions::PyIndexError::new_err("Cell out of bounds"));
+            return Err(Error::out_of_bounds("Cell out of bounds"));
         }
         let direction = match dir {
             0 => Direction::Top,
             1 => Direction::Right,
             2 => Direction::Bottom,
             3 => Direction::Left,
-            _ => return Err(pyo3::exceptions::PyIndexError::new_err("Invalid direction")),
+            _ => return Err(Error::out_of_bounds("Invalid direction")),
         };
-        self.grid[row][col].remove_wall(direction);
+        self.carve_passage(row, col, direction);
         Ok(())
     }
 
-    pub fn generate_maze(&mut self, exits: Option<Vec<String>>) -> PyResult<()> {
+    /// Carve the maze. The same `seed` and `algorithm` always yield the same walls and chests.
+    ///
+    /// `braid` is the fraction of dead ends (0.0 to 1.0) turned into loops after carving.
+    pub fn generate_maze(&mut self, exits: Option<Vec<String>>, seed: Option<u64>, algorithm: Option<Algorithm>, braid: f64) -> Result<()> {
+        if !(0.0..=1.0).contains(&braid) {
+            return Err(Error::invalid(format!("Braid ratio must be between 0 and 1, got {}", braid)));
+        }
         let exits = exits.unwrap_or_else(|| vec!["top".to_string(), "right".to_string(), "bottom".to_string(), "left".to_string()]);
-        let mut rng = thread_rng();
-        let mut stack = Vec::new();
-        let mut visited = vec![vec![false; self.width]; self.height];
-
-        stack.push((0, 0));
-        visited[0][0] = true;
-
-        while let Some((row, col)) = stack.pop() {
-            let mut neighbors = Vec::new();
-
-            if row > 0 && !visited[row - 1][col] {
-                neighbors.push((row - 1, col, Direction::Top, Direction::Bottom));
-            }
-            if col + 1 < self.width && !visited[row][col + 1] {
-                neighbors.push((row, col + 1, Direction::Right, Direction::Left));
-            }
-            if row + 1 < self.height && !visited[row + 1][col] {
-                neighbors.push((row + 1, col, Direction::Bottom, Direction::Top));
-            }
-            if col > 0 && !visited[row][col - 1] {
-                neighbors.push((row, col - 1, Direction::Left, Direction::Right));
-            }
+        let seed = seed.unwrap_or_else(rng::random_seed);
+        let mut rng = rng::stream_rng(seed, Stream::Layout);
 
-            if !neighbors.is_empty() {
-                stack.push((row, col));
-                let &(nrow, ncol, dir, opp_dir) = neighbors.choose(&mut rng).unwrap();
-                self.grid[row][col].remove_wall(dir);
-                self.grid[nrow][ncol].remove_wall(opp_dir);
-                visited[nrow][ncol] = true;
-                stack.push((nrow, ncol));
-            }
+        self.algorithm = algorithm.unwrap_or(Algorithm::RecursiveBacktracker);
+        self.grid = Grid::new(self.width, self.height);
+        self.algorithm.generator().carve(self, &mut rng);
+        if braid > 0.0 {
+            self.braid(braid, &mut rng);
         }
 
         self.add_exits(&exits)?;
-        self.spawn_chests();
+        self.spawn_chests(seed);
         Ok(())
     }
 
     pub fn display(&self) {
-        for row in 0..self.height {
-            for col in 0..self.width {
-                print!("{}", if self.grid[row][col].walls[0] { "+---" } else { "+   " });
-            }
-            println!("+");
+        print!("{}", ascii::render(self, false, None, &[]));
+    }
 
-            for col in 0..self.width {
-                print!("{}", if self.grid[row][col].walls[3] { "|   " } else { "    " });
-                if col == self.width - 1 {
-                    print!("{}", if self.grid[row][col].walls[1] { "|" } else { " " });
-                }
-            }
-            println!();
-        }
+    /// Draw the room in the `display` format, optionally marking chests, the player and enemies.
+    pub fn to_ascii(&self, chests: bool, player: Option<Entity>, enemies: Option<Vec<Entity>>) -> String {
+        ascii::render(self, chests, player.as_ref(), enemies.as_deref().unwrap_or(&[]))
+    }
 
-        for col in 0..self.width {
-            print!("{}", if self.grid[self.height - 1][col].walls[2] { "+---" } else { "+   " });
-        }
-        println!("+");
+    /// Parse a drawing made by `to_ascii` (or by hand). Chest glyphs become chests;
+    /// player and enemy markers are ignored, see `parse_ascii`.
+    pub fn from_ascii(text: &str) -> Result<Maze> {
+        Ok(ascii::parse(text)?.maze)
+    }
+
+    /// Like `from_ascii`, but also returns the player's and enemies' (x, y) positions.
+    pub fn parse_ascii(text: &str) -> Result<(Maze, Option<ascii::Position>, Vec<ascii::Position>)> {
+        let room = ascii::parse(text)?;
+        Ok((room.maze, room.player, room.enemies))
+    }
+
+    /// Export walls and chests as JSON (schema documented in `maze/json.rs`).
+    pub fn to_json(&self, pretty: bool) -> Result<String> {
+        json::to_json(self, pretty)
+    }
+
+    /// Import a room written by `to_json` or an external editor.
+    /// Rejects walls that one cell sees as open and its neighbour as closed.
+    pub fn from_json(data: &str) -> Result<Maze> {
+        json::from_json(data)
+    }
+
+    /// Draw the room as an SVG document.
+    pub fn to_svg(&self, options: Option<RenderOptions>, player: Option<Entity>, enemies: Option<Vec<Entity>>) -> String {
+        let occupants = Occupants { player: player.as_ref(), enemies: enemies.as_deref().unwrap_or(&[]) };
+        render::maze_svg(self, &occupants, &options.unwrap_or_default())
     }
 }
 
 impl Maze {
-    fn add_exits(&mut self, exits: &[String]) -> PyResult<()> {
+    /// Cell on the other side of `dir`, if it is inside the maze.
+    pub fn neighbor(&self, row: usize, col: usize, dir: Direction) -> Option<(usize, usize)> {
+        match dir {
+            Direction::Top if row > 0 => Some((row - 1, col)),
+            Direction::Right if col + 1 < self.width => Some((row, col + 1)),
+            Direction::Bottom if row + 1 < self.height => Some((row + 1, col)),
+            Direction::Left if col > 0 => Some((row, col - 1)),
+            _ => None,
+        }
+    }
+
+    /// Open the wall between a cell and its neighbour.
+    pub fn carve_passage(&mut self, row: usize, col: usize, dir: Direction) {
+        self.grid.set_wall(row, col, dir, false);
+    }
+
+    /// Close the wall between a cell and its neighbour.
+    pub fn build_wall(&mut self, row: usize, col: usize, dir: Direction) {
+        self.grid.set_wall(row, col, dir, true);
+    }
+
+    /// Every (row, col), row by row
+    fn cells(&self) -> impl Iterator<Item = (usize, usize)> + use<> {
+        let width = self.width;
+        (0..self.height).flat_map(move |row| (0..width).map(move |col| (row, col)))
+    }
+
+    /// Number of open walls around a cell.
+    fn open_sides(&self, row: usize, col: usize) -> usize {
+        Direction::ALL.iter().filter(|&&dir| !self.grid.has_wall(row, col, dir)).count()
+    }
+
+    /// Turn roughly `ratio` of the dead ends into loops by knocking out one more wall each.
+    /// Prefers joining two dead ends together so a single wall removes both.
+    pub fn braid(&mut self, ratio: f64, rng: &mut dyn RngCore) {
+        let mut dead_ends: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .filter(|&(row, col)| self.open_sides(row, col) == 1)
+            .collect();
+        dead_ends.shuffle(rng);
+
+        for (row, col) in dead_ends {
+            // An earlier removal may already have opened this one up
+            if self.open_sides(row, col) != 1 || !rng.gen_bool(ratio) {
+                continue;
+            }
+            let walled: Vec<_> = Direction::ALL
+                .iter()
+                .copied()
+                .filter(|&dir| self.grid.has_wall(row, col, dir) && self.neighbor(row, col, dir).is_some())
+                .collect();
+            let paired: Vec<_> = walled
+                .iter()
+                .copied()
+                .filter(|&dir| self.neighbor(row, col, dir).is_some_and(|(r, c)| self.open_sides(r, c) == 1))
+                .collect();
+            let pick = if paired.is_empty() { walled.choose(rng) } else { paired.choose(rng) };
+            if let Some(&dir) = pick {
+                self.carve_passage(row, col, dir);
+            }
+        }
+    }
+
+    fn add_exits(&mut self, exits: &[String]) -> Result<()> {
         let mid_row = self.height / 2;
         let mid_col = self.width / 2;
 
         for exit in exits {
             match exit.as_str() {
-                "top" => self.grid[0][mid_col].remove_wall(Direction::Top),
-                "bottom" => self.grid[self.height - 1][mid_col].remove_wall(Direction::Bottom),
-                "left" => self.grid[mid_row][0].remove_wall(Direction::Left),
-                "right" => self.grid[mid_row][self.width - 1].remove_wall(Direction::Right),
-                _ => return Err(pyo3::exceptions::PyValueError::new_err(format!("Invalid exit: {}", exit))),
+                "top" => self.carve_passage(0, mid_col, Direction::Top),
+                "bottom" => self.carve_passage(self.height - 1, mid_col, Direction::Bottom),
+                "left" => self.carve_passage(mid_row, 0, Direction::Left),
+                "right" => self.carve_passage(mid_row, self.width - 1, Direction::Right),
+                _ => return Err(Error::invalid(format!("Invalid exit: {}", exit))),
             }
         }
 
         Ok(())
     }
 
+    /// Mark everything in line of sight of (x, y) as explored
+    pub fn reveal(&mut self, x: usize, y: usize) {
+        for (row, seen) in sight::visible_from(self, x, y).into_iter().enumerate() {
+            for (col, seen) in seen.into_iter().enumerate() {
+                if seen {
+                    self.grid.set_visited(row, col);
+                }
+            }
+        }
+    }
+
     pub fn can_move(&self, row: usize, col: usize, dir: usize) -> bool {
         if row >= self.height || col >= self.width {
             return false;
@@ -153,27 +307,56 @@ impl Maze {
             3 => Direction::Left,
             _ => return false,
         };
-        !self.grid[row][col].has_wall(direction)
+        !self.grid.has_wall(row, col, direction)
+    }
+
+    /// Make sure one chest in this room holds a key: reuse a chest if there is one,
+    /// otherwise put a new one in a dead end (or any cell that isn't an exit).
+    /// Cells in `avoid`, as (row, col), are left alone, e.g. where the player starts.
+    pub fn place_key(&mut self, seed: u64, avoid: &[(usize, usize)]) {
+        if self.chests.iter().any(|c| matches!(c.contents, Some(ChestContent::Key { }))) {
+            return;
+        }
+        let mut rng = rng::stream_rng(seed, Stream::Keys);
+        let reusable: Vec<usize> = (0..self.chests.len()).filter(|&i| !avoid.contains(&(self.chests[i].row, self.chests[i].col))).collect();
+        if !reusable.is_empty() {
+            let idx = reusable[rng.gen_range(0..reusable.len())];
+            self.chests[idx].contents = Some(ChestContent::Key { });
+            return;
+        }
+
+        let cells: Vec<(usize, usize)> = (0..self.height)
+            .flat_map(|row| (0..self.width).map(move |col| (row, col)))
+            .filter(|cell| !avoid.contains(cell) && !self.chests.iter().any(|c| (c.row, c.col) == *cell))
+            .collect();
+        let on_exit = |&(row, col): &(usize, usize)| {
+            let on_edge = [row == 0, col + 1 == self.width, row + 1 == self.height, col == 0];
+            (0..4).any(|dir| on_edge[dir] && self.can_move(row, col, dir))
+        };
+        let dead_ends: Vec<_> = cells.iter().copied().filter(|&(r, c)| self.open_sides(r, c) == 1).collect();
+        let others: Vec<_> = cells.iter().copied().filter(|cell| !on_exit(cell)).collect();
+        let spot = dead_ends.choose(&mut rng).or_else(|| others.choose(&mut rng)).or(cells.first()).copied();
+        if let Some((row, col)) = spot {
+            self.chests.push(Chest::new(row, col, Some(ChestContent::Key { })));
+        }
     }
 
     // Find dead ends and spawn chests there
-    fn spawn_chests(&mut self) {
-        let mut rng = thread_rng();
+    fn spawn_chests(&mut self, seed: u64) {
+        let mut rng = rng::stream_rng(seed, Stream::Loot);
         self.chests.clear();
         for row in 0..self.height {
             for col in 0..self.width {
-                let cell = &self.grid[row][col];
                 // Dead end: only one open wall
-                let open_walls = cell.walls.iter().filter(|&&w| !w).count();
-                if open_walls == 1 {
+                if self.open_sides(row, col) == 1 {
                     // Randomly decide to spawn a chest (e.g., 50% chance)
-                    if rand::random::<f32>() < 0.5 {
-                        let contents = Some(match rand::random::<u8>() % 5 {
+                    if rng.gen_bool(0.5) {
+                        // Keys are placed by the dungeon, never rolled as loot
+                        let contents = Some(match rng.gen_range(0..4u8) {
                             0 => ChestContent::Gold { amount: rng.gen_range(10..=100) },
                             1 => ChestContent::Sword { },
                             2 => ChestContent::Shield { },
-                            3 => ChestContent::Potion { },
-                            _ => ChestContent::Key { },
+                            _ => ChestContent::Potion { },
                         });
                         self.chests.push(Chest::new(row, col, contents));
                     }
@@ -181,4 +364,46 @@ impl Maze {
             }
         }
     }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn carved(width: usize, height: usize, seed: u64, algorithm: Algorithm, braid: f64) -> Maze {
+        let mut maze = Maze::new(width, height);
+        maze.generate_maze(Some(Vec::new()), Some(seed), Some(algorithm), braid).unwrap();
+        maze
+    }
+
+    fn dead_ends(maze: &Maze) -> usize {
+        maze.cells().filter(|&(row, col)| maze.open_sides(row, col) == 1).count()
+    }
+
+    #[test]
+    fn no_braiding_keeps_the_maze_perfect() {
+        for algorithm in Algorithm::ALL {
+            let maze = carved(9, 7, 5, algorithm, 0.0);
+            // A spanning tree: each of its cells - 1 passages opens one side of two cells
+            let open: usize = maze.cells().map(|(row, col)| maze.open_sides(row, col)).sum();
+            assert_eq!(open, 2 * (9 * 7 - 1), "{:?}", algorithm);
+        }
+    }
+
+    #[test]
+    fn full_braiding_leaves_no_dead_ends() {
+        for algorithm in Algorithm::ALL {
+            for seed in 0..5 {
+                assert!(dead_ends(&carved(9, 7, seed, algorithm, 0.0)) > 0);
+                assert_eq!(dead_ends(&carved(9, 7, seed, algorithm, 1.0)), 0, "{:?} seed {}", algorithm, seed);
+            }
+        }
+    }
+
+    #[test]
+    fn braiding_is_seeded() {
+        let a = carved(9, 7, 11, Algorithm::Prim, 0.5);
+        assert_eq!(a.wall_masks(), carved(9, 7, 11, Algorithm::Prim, 0.5).wall_masks());
+        assert_ne!(a.wall_masks(), carved(9, 7, 12, Algorithm::Prim, 0.5).wall_masks());
+        assert!(dead_ends(&a) < dead_ends(&carved(9, 7, 11, Algorithm::Prim, 0.0)));
+    }
+}
@@ -0,0 +1,125 @@
+// --- Line of sight ---
+//
+// A cell is visible when a straight line from the centre of the viewer's cell to the
+// centre of the target crosses no wall. A line that passes exactly through a corner
+// gets by if it could squeeze past on either side of it.
+use super::Maze;
+
+/// Cells visible from (x, y), indexed [row][col]. All false if (x, y) is outside the room.
+pub fn visible_from(maze: &Maze, x: usize, y: usize) -> Vec<Vec<bool>> {
+    let mut seen = vec![vec![false; maze.width]; maze.height];
+    if x >= maze.width || y >= maze.height {
+        return seen;
+    }
+    for (row, cells) in seen.iter_mut().enumerate() {
+        for (col, cell) in cells.iter_mut().enumerate() {
+            *cell = line_of_sight(maze, (x, y), (col, row));
+        }
+    }
+    seen
+}
+
+/// Walk the grid cells a line between two cell centres passes through, checking
+/// each wall it crosses.
+fn line_of_sight(maze: &Maze, from: (usize, usize), to: (usize, usize)) -> bool {
+    let (nx, ny) = (from.0.abs_diff(to.0) as i64, from.1.abs_diff(to.1) as i64);
+    let horizontal = if to.0 > from.0 { 1 } else { 3 };
+    let vertical = if to.1 > from.1 { 2 } else { 0 };
+    let (mut x, mut y) = from;
+    let (mut ix, mut iy) = (0, 0);
+    let step = |x: usize, dir: usize| if dir == 1 || dir == 2 { x + 1 } else { x - 1 };
+
+    while ix < nx || iy < ny {
+        // Which cell boundary the line reaches first; 0 means both at once (a corner)
+        let decision = (1 + 2 * ix) * ny - (1 + 2 * iy) * nx;
+        if decision == 0 {
+            let across_then_down = maze.can_move(y, x, horizontal) && maze.can_move(y, step(x, horizontal), vertical);
+            let down_then_across = maze.can_move(y, x, vertical) && maze.can_move(step(y, vertical), x, horizontal);
+            if !across_then_down && !down_then_across {
+                return false;
+            }
+            x = step(x, horizontal);
+            y = step(y, vertical);
+            ix += 1;
+            iy += 1;
+        } else if decision < 0 {
+            if !maze.can_move(y, x, horizontal) {
+                return false;
+            }
+            x = step(x, horizontal);
+            ix += 1;
+        } else {
+            if !maze.can_move(y, x, vertical) {
+                return false;
+            }
+            y = step(y, vertical);
+            iy += 1;
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A room with every inner wall knocked down
+    fn open_room(width: usize, height: usize) -> Maze {
+        let mut maze = Maze::new(width, height);
+        for row in 0..height {
+            for col in 0..width {
+                if col + 1 < width {
+                    maze.remove_wall(row, col, 1).unwrap();
+                }
+                if row + 1 < height {
+                    maze.remove_wall(row, col, 2).unwrap();
+                }
+            }
+        }
+        maze
+    }
+
+    #[test]
+    fn open_rooms_are_seen_whole() {
+        let maze = open_room(6, 4);
+        assert!(visible_from(&maze, 0, 3).iter().flatten().all(|&seen| seen));
+        assert!(visible_from(&maze, 6, 0).iter().flatten().all(|&seen| !seen));
+    }
+
+    #[test]
+    fn walls_block_the_view() {
+        let mut maze = Maze::new(5, 1);
+        for col in [0, 1, 3] {
+            maze.remove_wall(0, col, 1).unwrap();
+        }
+        assert_eq!(visible_from(&maze, 0, 0), vec![vec![true, true, true, false, false]]);
+        assert_eq!(visible_from(&maze, 4, 0), vec![vec![false, false, false, true, true]]);
+    }
+
+    #[test]
+    fn corners_let_a_line_squeeze_past_one_side() {
+        // Across the top, then down the right: the diagonal gets by
+        let mut bend = Maze::new(2, 2);
+        bend.remove_wall(0, 0, 1).unwrap();
+        bend.remove_wall(0, 1, 2).unwrap();
+        assert!(visible_from(&bend, 0, 0)[1][1]);
+        // Two separate corridors: walls on both sides of the corner
+        let mut split = Maze::new(2, 2);
+        split.remove_wall(0, 0, 1).unwrap();
+        split.remove_wall(1, 0, 1).unwrap();
+        assert!(!visible_from(&split, 0, 0)[1][1]);
+    }
+
+    #[test]
+    fn revealed_cells_stay_explored() {
+        let mut maze = Maze::new(5, 1);
+        for col in [0, 1, 3] {
+            maze.remove_wall(0, col, 1).unwrap();
+        }
+        assert_eq!(maze.explored_fraction(), 0.0);
+        maze.reveal(0, 0);
+        assert_eq!(maze.explored_cells(), vec![vec![true, true, true, false, false]]);
+        maze.reveal(4, 0);
+        assert_eq!(maze.explored_fraction(), 1.0);
+    }
+}
@@ -0,0 +1,301 @@
+// --- Pathfinding inside a room ---
+//
+// Everything here walks one `Maze` through open walls, with positions as (x, y):
+//
+//   astar         one shortest path between two cells
+//   DistanceMap   moves from a set of cells to every other cell (a "Dijkstra map"),
+//                 optionally with cells that take an extra move to enter
+//   FlowField     for every cell, the way to go to get one step closer to those cells
+//
+// A distance map costs one pass over the room however many walkers use it, which is
+// why all the enemies chasing the player share a single flow field each turn. Routes
+// that cross rooms are planned on top of these in `dungeon::route`.
+use std::cmp::{Ordering, Reverse};
+use std::collections::{BinaryHeap, HashMap};
+use crate::maze::Maze;
+
+/// A cell, as (x, y)
+pub type Pos = (usize, usize);
+
+/// Neighbouring (x, y) in direction `dir` (0 up, 1 right, 2 down, 3 left),
+/// if no wall is in the way and it is still inside the room
+pub fn open_step(pos: Pos, dir: usize, maze: &Maze) -> Option<Pos> {
+    let (x, y) = pos;
+    let next = match dir {
+        0 if y > 0 => (x, y - 1),
+        1 if x + 1 < maze.width => (x + 1, y),
+        2 if y + 1 < maze.height => (x, y + 1),
+        3 if x > 0 => (x - 1, y),
+        _ => return None,
+    };
+    maze.can_move(y, x, dir).then_some(next)
+}
+
+fn manhattan(a: Pos, b: Pos) -> usize {
+    a.0.abs_diff(b.0) + a.1.abs_diff(b.1)
+}
+
+/// A* search from `start` to `goal`. `neighbors` lists the cells reachable from a cell
+/// with the cost of each step, and `heuristic` must never overestimate the cost left.
+/// Returns the path with both ends included.
+pub fn astar<F, H>(start: Pos, goal: Pos, mut neighbors: F, mut heuristic: H) -> Option<Vec<Pos>>
+where
+    F: FnMut(Pos) -> Vec<(Pos, usize)>,
+    H: FnMut(Pos) -> usize,
+{
+    #[derive(Eq)]
+    struct Node {
+        pos: Pos,
+        cost: usize,
+        est: usize,
+    }
+    impl Ord for Node {
+        fn cmp(&self, other: &Self) -> Ordering {
+            (other.cost + other.est).cmp(&(self.cost + self.est))
+        }
+    }
+    impl PartialOrd for Node {
+        fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+            Some(self.cmp(other))
+        }
+    }
+    impl PartialEq for Node {
+        fn eq(&self, other: &Self) -> bool {
+            self.pos == other.pos && self.cost + self.est == other.cost + other.est
+        }
+    }
+
+    let mut heap = BinaryHeap::new();
+    let mut came_from = HashMap::new();
+    let mut cost_so_far = HashMap::new();
+
+    heap.push(Node { pos: start, cost: 0, est: heuristic(start) });
+    cost_so_far.insert(start, 0);
+
+    while let Some(Node { pos, cost, .. }) = heap.pop() {
+        if pos == goal {
+            // Reconstruct path
+            let mut path = vec![pos];
+            let mut current = pos;
+            while let Some(&prev) = came_from.get(&current) {
+                path.push(prev);
+                current = prev;
+            }
+            path.reverse();
+            return Some(path);
+        }
+        for (next, step_cost) in neighbors(pos) {
+            let new_cost = cost + step_cost;
+            if cost_so_far.get(&next).is_none_or(|&c| new_cost < c) {
+                cost_so_far.insert(next, new_cost);
+                let est = heuristic(next);
+                heap.push(Node { pos: next, cost: new_cost, est });
+                came_from.insert(next, pos);
+            }
+        }
+    }
+    None
+}
+
+/// Shortest walk from `from` to `to` inside `maze`, both ends included.
+/// None if a wall cuts them off (or either is outside the room).
+pub fn shortest_path(maze: &Maze, from: Pos, to: Pos) -> Option<Vec<Pos>> {
+    let inside = |(x, y): Pos| x < maze.width && y < maze.height;
+    if !inside(from) || !inside(to) {
+        return None;
+    }
+    astar(
+        from,
+        to,
+        |pos| (0..4).filter_map(|dir| open_step(pos, dir, maze)).map(|next| (next, 1)).collect(),
+        |pos| manhattan(pos, to),
+    )
+}
+
+/// Moves from the nearest of a set of source cells to every cell of a room.
+#[derive(Clone, Debug)]
+pub struct DistanceMap {
+    width: usize,
+    height: usize,
+    steps: Vec<Option<u32>>,
+    /// Cells that take an extra move to step into
+    slow: Vec<bool>,
+}
+
+impl DistanceMap {
+    /// Breadth-first from every source at once. Sources outside the room are skipped.
+    pub fn new(maze: &Maze, sources: &[Pos]) -> Self {
+        Self::weighted(maze, sources, &[])
+    }
+
+    /// Like `new`, but stepping into one of the `slow` cells costs two moves instead of
+    /// one, e.g. a closed chest that has to be opened before it can be walked over.
+    pub fn weighted(maze: &Maze, sources: &[Pos], slow: &[Pos]) -> Self {
+        let (width, height) = (maze.width, maze.height);
+        let mut map = DistanceMap { width, height, steps: vec![None; width * height], slow: vec![false; width * height] };
+        for &(x, y) in slow {
+            if x < width && y < height {
+                map.slow[y * width + x] = true;
+            }
+        }
+        // Dijkstra; with every step costing one move this visits cells in BFS order
+        let mut heap = BinaryHeap::new();
+        for &(x, y) in sources {
+            if x < width && y < height && map.steps[y * width + x].is_none() {
+                map.steps[y * width + x] = Some(0);
+                heap.push(Reverse((0, (x, y))));
+            }
+        }
+        while let Some(Reverse((d, pos))) = heap.pop() {
+            if map.steps[pos.1 * width + pos.0].is_some_and(|best| best < d) {
+                continue;
+            }
+            for dir in 0..4 {
+                if let Some(next) = open_step(pos, dir, maze) {
+                    let i = next.1 * width + next.0;
+                    let cost = d + map.entry_cost(next);
+                    if map.steps[i].is_none_or(|best| cost < best) {
+                        map.steps[i] = Some(cost);
+                        heap.push(Reverse((cost, next)));
+                    }
+                }
+            }
+        }
+        map
+    }
+
+    /// Moves from `pos` to the nearest source; None if it can't be reached
+    pub fn get(&self, (x, y): Pos) -> Option<u32> {
+        if x < self.width && y < self.height { self.steps[y * self.width + x] } else { None }
+    }
+
+    /// Moves it takes to step into `pos`
+    fn entry_cost(&self, (x, y): Pos) -> u32 {
+        if self.slow[y * self.width + x] { 2 } else { 1 }
+    }
+
+    /// The whole map, indexed [row][col]
+    pub fn rows(&self) -> Vec<Vec<Option<u32>>> {
+        self.steps.chunks(self.width.max(1)).map(<[_]>::to_vec).collect()
+    }
+
+    /// Direction out of `pos` that is one step closer to a source. Ties go to the
+    /// first in up, right, down, left order. None on a source or a cut-off cell.
+    pub fn downhill(&self, maze: &Maze, pos: Pos) -> Option<usize> {
+        let here = self.get(pos).filter(|&d| d > 0)?;
+        let before = here.checked_sub(self.entry_cost(pos))?;
+        (0..4).find(|&dir| open_step(pos, dir, maze).is_some_and(|next| self.get(next) == Some(before)))
+    }
+
+    /// Walk downhill from `from` to the nearest source, both ends included
+    pub fn path(&self, maze: &Maze, from: Pos) -> Option<Vec<Pos>> {
+        self.get(from)?;
+        let mut path = vec![from];
+        while let Some(dir) = self.downhill(maze, path[path.len() - 1]) {
+            let next = open_step(path[path.len() - 1], dir, maze).expect("downhill steps are open");
+            path.push(next);
+        }
+        Some(path)
+    }
+}
+
+/// `DistanceMap::downhill` worked out for every cell in advance, for many walkers to share.
+#[derive(Clone, Debug)]
+pub struct FlowField {
+    width: usize,
+    height: usize,
+    /// 0 up, 1 right, 2 down, 3 left; None on the sources and on cells cut off from them
+    directions: Vec<Option<u8>>,
+}
+
+impl FlowField {
+    pub fn new(maze: &Maze, map: &DistanceMap) -> Self {
+        let directions = (0..maze.height)
+            .flat_map(|y| (0..maze.width).map(move |x| (x, y)))
+            .map(|pos| map.downhill(maze, pos).map(|dir| dir as u8))
+            .collect();
+        FlowField { width: maze.width, height: maze.height, directions }
+    }
+
+    pub fn direction(&self, (x, y): Pos) -> Option<usize> {
+        if x < self.width && y < self.height { self.directions[y * self.width + x].map(usize::from) } else { None }
+    }
+
+    /// The cell one step closer to the sources; None when already there or cut off
+    pub fn next(&self, (x, y): Pos) -> Option<Pos> {
+        self.direction((x, y)).map(|dir| match dir {
+            0 => (x, y - 1),
+            1 => (x + 1, y),
+            2 => (x, y + 1),
+            _ => (x - 1, y),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A 3x3 room: a U-shaped corridor from (0, 0) round to (0, 1), with the bottom row walled off.
+    ///
+    ///   0 - 1 - 2
+    ///           |
+    ///   0 - 1 - 2
+    fn u_room() -> Maze {
+        let mut maze = Maze::new(3, 3);
+        for (row, col, dir) in [(0, 0, 1), (0, 1, 1), (0, 2, 2), (1, 2, 3), (1, 1, 3)] {
+            maze.remove_wall(row, col, dir).unwrap();
+        }
+        maze
+    }
+
+    #[test]
+    fn astar_walks_around_walls() {
+        let maze = u_room();
+        let path = shortest_path(&maze, (0, 0), (0, 1)).unwrap();
+        assert_eq!(path, vec![(0, 0), (1, 0), (2, 0), (2, 1), (1, 1), (0, 1)]);
+        assert_eq!(shortest_path(&maze, (0, 0), (0, 0)), Some(vec![(0, 0)]));
+        assert_eq!(shortest_path(&maze, (0, 0), (1, 2)), None);
+        assert_eq!(shortest_path(&maze, (0, 0), (3, 0)), None);
+    }
+
+    #[test]
+    fn distance_maps_count_moves_from_the_nearest_source() {
+        let maze = u_room();
+        let map = DistanceMap::new(&maze, &[(0, 0)]);
+        assert_eq!(map.get((0, 1)), Some(5));
+        assert_eq!(map.get((1, 2)), None);
+        assert_eq!(map.get((9, 9)), None);
+        assert_eq!(map.path(&maze, (0, 1)).unwrap(), shortest_path(&maze, (0, 1), (0, 0)).unwrap());
+
+        let both = DistanceMap::new(&maze, &[(0, 0), (0, 1)]);
+        assert_eq!(both.rows()[0], vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(both.rows()[1], vec![Some(0), Some(1), Some(2)]);
+        assert_eq!(both.downhill(&maze, (0, 1)), None);
+    }
+
+    #[test]
+    fn slow_cells_cost_an_extra_move() {
+        let maze = u_room();
+        let map = DistanceMap::weighted(&maze, &[(0, 0)], &[(1, 0)]);
+        assert_eq!(map.get((1, 0)), Some(2));
+        assert_eq!(map.get((0, 1)), Some(6));
+        // Leaving a slow cell is free, so the source itself being slow changes nothing
+        assert_eq!(DistanceMap::weighted(&maze, &[(0, 0)], &[(0, 0)]).get((0, 1)), Some(5));
+        assert_eq!(map.path(&maze, (0, 1)).unwrap().len(), 6);
+    }
+
+    #[test]
+    fn flow_fields_lead_every_walker_to_the_source() {
+        let maze = u_room();
+        let field = FlowField::new(&maze, &DistanceMap::new(&maze, &[(0, 0)]));
+        assert_eq!(field.direction((0, 0)), None);
+        assert_eq!(field.direction((1, 2)), None);
+        assert_eq!(field.direction((2, 1)), Some(0));
+        let mut pos = (0, 1);
+        for _ in 0..5 {
+            pos = field.next(pos).unwrap();
+        }
+        assert_eq!(pos, (0, 0));
+    }
+}
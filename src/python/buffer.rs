@@ -0,0 +1,78 @@
+// --- numpy arrays over Rust-built bytes ---
+//
+// `Buffer` owns bytes built on the Rust side and lends them to Python through the
+// buffer protocol, so `numpy.asarray` wraps them instead of copying them a second time.
+// The array holds a reference to the buffer, which keeps the bytes alive for as long as
+// it is around. The bytes are a snapshot taken when the array was made: later moves
+// don't show up in it.
+use std::ffi::{CStr, c_int, c_void};
+use std::ptr;
+use pyo3::exceptions::PyBufferError;
+use pyo3::ffi;
+use pyo3::prelude::*;
+
+#[pyclass(module = "dungeon_core")]
+pub struct Buffer {
+    data: Vec<u8>,
+    /// struct-module format of one item
+    format: &'static CStr,
+    itemsize: usize,
+    shape: Vec<isize>,
+    strides: Vec<isize>,
+}
+
+impl Buffer {
+    fn new(data: Vec<u8>, format: &'static CStr, itemsize: usize, shape: &[usize]) -> Self {
+        debug_assert_eq!(data.len(), shape.iter().product::<usize>() * itemsize);
+        // C order: the last axis is contiguous
+        let mut strides = vec![itemsize as isize; shape.len()];
+        for i in (0..shape.len().saturating_sub(1)).rev() {
+            strides[i] = strides[i + 1] * shape[i + 1] as isize;
+        }
+        Buffer { data, format, itemsize, shape: shape.iter().map(|&n| n as isize).collect(), strides }
+    }
+
+    fn into_array<'py>(self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        py.import_bound("numpy")?.call_method1("asarray", (Bound::new(py, self)?,))
+    }
+}
+
+/// A numpy uint8 array of `shape` over `data`, without copying it again.
+pub fn u8_array<'py>(py: Python<'py>, data: Vec<u8>, shape: &[usize]) -> PyResult<Bound<'py, PyAny>> {
+    Buffer::new(data, c"B", 1, shape).into_array(py)
+}
+
+pub fn f32_array<'py>(py: Python<'py>, data: &[f32], shape: &[usize]) -> PyResult<Bound<'py, PyAny>> {
+    let bytes = data.iter().flat_map(|v| v.to_ne_bytes()).collect();
+    Buffer::new(bytes, c"f", 4, shape).into_array(py)
+}
+
+pub fn bool_array<'py>(py: Python<'py>, data: &[bool], shape: &[usize]) -> PyResult<Bound<'py, PyAny>> {
+    Buffer::new(data.iter().map(|&b| u8::from(b)).collect(), c"?", 1, shape).into_array(py)
+}
+
+#[pymethods]
+impl Buffer {
+    unsafe fn __getbuffer__(slf: Bound<'_, Self>, view: *mut ffi::Py_buffer, flags: c_int) -> PyResult<()> {
+        if view.is_null() {
+            return Err(PyBufferError::new_err("View is null"));
+        }
+        // Nothing on the Rust side touches the bytes again, so Python may write to them
+        let mut buffer = slf.try_borrow_mut()?;
+        (*view).buf = buffer.data.as_mut_ptr() as *mut c_void;
+        (*view).len = buffer.data.len() as isize;
+        (*view).readonly = 0;
+        (*view).itemsize = buffer.itemsize as isize;
+        (*view).format = if flags & ffi::PyBUF_FORMAT == ffi::PyBUF_FORMAT { buffer.format.as_ptr() as *mut _ } else { ptr::null_mut() };
+        (*view).ndim = buffer.shape.len() as c_int;
+        (*view).shape = if flags & ffi::PyBUF_ND == ffi::PyBUF_ND { buffer.shape.as_mut_ptr() } else { ptr::null_mut() };
+        (*view).strides = if flags & ffi::PyBUF_STRIDES == ffi::PyBUF_STRIDES { buffer.strides.as_mut_ptr() } else { ptr::null_mut() };
+        (*view).suboffsets = ptr::null_mut();
+        (*view).internal = ptr::null_mut();
+        drop(buffer);
+        (*view).obj = slf.into_any().into_ptr();
+        Ok(())
+    }
+
+    unsafe fn __releasebuffer__(&self, _view: *mut ffi::Py_buffer) {}
+}
@@ -0,0 +1,522 @@
+// --- Dungeon bindings ---
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use std::collections::HashMap;
+use super::buffer;
+use crate::dungeon::Dungeon;
+use crate::dungeon::boss::BossPhase;
+use crate::dungeon::door::{Door, Room};
+use crate::dungeon::room::Respawn;
+use crate::dungeon::route::{Route, Target};
+use crate::dungeon::shop::Shop;
+use crate::dungeon::state::GameState;
+use crate::dungeon::validate::Diagnostic;
+use crate::entity::Entity;
+use crate::entity::inventory::{Item, Slot};
+use crate::error::Result;
+use crate::maze::Maze;
+use crate::maze::generator::Algorithm;
+use crate::render::{self, RenderOptions};
+
+#[pymethods]
+impl Dungeon {
+    /// `room_algorithms` maps (row, col) to an Algorithm. Raises ValueError for zero sizes or a
+    /// player outside the room, IndexError for a `room_algorithms` key off the grid.
+    #[new]
+    #[pyo3(signature = (rows, cols, maze_width, maze_height, player, seed=None, algorithms=None, room_algorithms=None, braid=0.0, respawn=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        rows: usize,
+        cols: usize,
+        maze_width: usize,
+        maze_height: usize,
+        player: Entity,
+        seed: Option<u64>,
+        algorithms: Option<Vec<Algorithm>>,
+        room_algorithms: Option<HashMap<Room, Algorithm>>,
+        braid: f64,
+        respawn: Option<Respawn>,
+    ) -> Result<Self> {
+        Dungeon::new(rows, cols, maze_width, maze_height, player, seed, algorithms, room_algorithms, braid, respawn)
+    }
+
+    #[getter]
+    fn mazes(&self) -> Vec<Vec<Maze>> {
+        self.mazes.clone()
+    }
+
+    #[setter]
+    fn set_mazes(&mut self, mazes: Vec<Vec<Maze>>) {
+        self.mazes = mazes;
+    }
+
+    #[getter]
+    fn player(&self) -> Entity {
+        self.player.clone()
+    }
+
+    #[setter]
+    fn set_player(&mut self, player: Entity) {
+        self.player = player;
+    }
+
+    #[getter]
+    fn current_room_row(&self) -> usize {
+        self.current_room_row
+    }
+
+    #[setter]
+    fn set_current_room_row(&mut self, row: usize) {
+        self.current_room_row = row;
+    }
+
+    #[getter]
+    fn current_room_col(&self) -> usize {
+        self.current_room_col
+    }
+
+    #[setter]
+    fn set_current_room_col(&mut self, col: usize) {
+        self.current_room_col = col;
+    }
+
+    #[getter]
+    fn enemies(&self) -> Vec<Entity> {
+        self.enemies.clone()
+    }
+
+    #[setter]
+    fn set_enemies(&mut self, enemies: Vec<Entity>) {
+        self.enemies = enemies;
+    }
+
+    #[getter]
+    fn seed(&self) -> u64 {
+        self.seed
+    }
+
+    /// list[list[bool]], [row][col]
+    #[getter]
+    fn visited_rooms(&self) -> Vec<Vec<bool>> {
+        self.visited_rooms.clone()
+    }
+
+    /// Keys the player must hold for `exit_unlocked`
+    #[getter]
+    fn keys_required(&self) -> u32 {
+        self.keys_required
+    }
+
+    /// (row, col)
+    #[getter]
+    fn exit_room(&self) -> Room {
+        self.exit_room
+    }
+
+    /// (x, y) inside `exit_room`
+    #[getter]
+    fn exit_cell(&self) -> (usize, usize) {
+        self.exit_cell
+    }
+
+    #[getter(exit_unlocked)]
+    fn py_exit_unlocked(&self) -> bool {
+        self.exit_unlocked()
+    }
+
+    #[getter]
+    fn state(&self) -> GameState {
+        self.state
+    }
+
+    /// Enemy turns so far; `Respawn.after_turns` counts in these
+    #[getter]
+    fn turn(&self) -> u64 {
+        self.turn
+    }
+
+    /// list of (row, col)
+    #[getter]
+    fn boss_rooms(&self) -> Vec<Room> {
+        self.boss_rooms.clone()
+    }
+
+    /// list of (row, col), a subset of `boss_rooms`
+    #[getter]
+    fn defeated_bosses(&self) -> Vec<Room> {
+        self.defeated_bosses.clone()
+    }
+
+    /// None unless a boss is alive in the current room
+    #[getter]
+    fn boss_phase(&self) -> Option<BossPhase> {
+        self.boss_phase
+    }
+
+    /// list of Door; copies, so editing them changes nothing
+    #[getter]
+    fn doors(&self) -> Vec<Door> {
+        self.doors.clone()
+    }
+
+    /// list of Shop; copies, trade with `buy` and `sell`
+    #[getter]
+    fn shops(&self) -> Vec<Shop> {
+        self.shops.clone()
+    }
+
+    /// Settable, e.g. `dungeon.respawn = Respawn.never()`
+    #[getter]
+    fn respawn(&self) -> Respawn {
+        self.respawn.clone()
+    }
+
+    #[setter]
+    fn set_respawn(&mut self, respawn: Respawn) {
+        self.respawn = respawn;
+    }
+
+    /// Adds `count` enemies to the current room; `move_player` already does this on entry
+    #[pyo3(name = "spawn_enemies")]
+    fn py_spawn_enemies(&mut self, count: usize) {
+        self.spawn_enemies(count);
+    }
+
+    /// Rooms between the spawn room and this one
+    #[pyo3(name = "floor")]
+    fn py_floor(&self) -> usize {
+        self.floor()
+    }
+
+    #[pyo3(name = "move_enemies")]
+    fn py_move_enemies(&mut self) {
+        self.move_enemies();
+    }
+
+    /// `direction` is "up", "right", "down" or "left"; returns False if the move was refused
+    #[pyo3(name = "move_player")]
+    fn py_move_player(&mut self, direction: &str) -> Result<bool> {
+        self.move_player(direction)
+    }
+
+    /// list of Diagnostic; empty when the dungeon is playable
+    #[pyo3(name = "validate")]
+    fn py_validate(&self) -> Vec<Diagnostic> {
+        self.validate()
+    }
+
+    /// Same arguments as `Dungeon()`; raises ValueError if no seed passes `validate` in `max_attempts`
+    #[staticmethod]
+    #[pyo3(name = "generate_valid", signature = (rows, cols, maze_width, maze_height, player, seed=None, algorithms=None, room_algorithms=None, braid=0.0, respawn=None, max_attempts=10))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_generate_valid(
+        rows: usize,
+        cols: usize,
+        maze_width: usize,
+        maze_height: usize,
+        player: Entity,
+        seed: Option<u64>,
+        algorithms: Option<Vec<Algorithm>>,
+        room_algorithms: Option<HashMap<Room, Algorithm>>,
+        braid: f64,
+        respawn: Option<Respawn>,
+        max_attempts: usize,
+    ) -> Result<Self> {
+        Dungeon::generate_valid(rows, cols, maze_width, maze_height, player, seed, algorithms, room_algorithms, braid, respawn, max_attempts)
+    }
+
+    /// str holding one SVG document with every room
+    #[pyo3(name = "to_svg", signature = (options=None))]
+    fn py_to_svg(&self, options: Option<RenderOptions>) -> String {
+        self.to_svg(options)
+    }
+
+    /// bytes, ready for `pygame.image.load(io.BytesIO(...))`
+    #[pyo3(signature = (options=None))]
+    fn to_png<'py>(&self, py: Python<'py>, options: Option<RenderOptions>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &render::dungeon_png(self, &options.unwrap_or_default())?))
+    }
+
+    /// Same format as `to_bytes` and pickle
+    #[pyo3(name = "save")]
+    fn py_save(&self, path: &str) -> Result<()> {
+        self.save(path)
+    }
+
+    /// Raises OSError if the file can't be read, ValueError if it isn't a save
+    #[staticmethod]
+    #[pyo3(name = "load")]
+    fn py_load(path: &str) -> Result<Self> {
+        Dungeon::load(path)
+    }
+
+    #[pyo3(name = "to_bytes")]
+    fn py_to_bytes<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &self.to_bytes()?))
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_bytes")]
+    fn py_from_bytes(data: &[u8]) -> Result<Self> {
+        Dungeon::from_bytes(data)
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        self.py_to_bytes(py)
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = Dungeon::from_bytes(state)?;
+        Ok(())
+    }
+
+    /// Smallest dungeon the player fits in; `__setstate__` replaces it straight away
+    fn __getnewargs__(&self) -> (usize, usize, usize, usize, Entity) {
+        (1, 1, self.player.x + 1, self.player.y + 1, self.player.clone())
+    }
+
+    /// Returns False if the game isn't `Playing`; raises ValueError if the bag has none
+    #[pyo3(name = "use_item")]
+    fn py_use_item(&mut self, item: Item) -> Result<bool> {
+        self.use_item(item)
+    }
+
+    #[pyo3(name = "equip")]
+    fn py_equip(&mut self, item: Item) -> Result<()> {
+        self.equip(item)
+    }
+
+    #[pyo3(name = "unequip")]
+    fn py_unequip(&mut self, slot: Slot) -> bool {
+        self.unequip(slot)
+    }
+
+    #[pyo3(name = "drop_item", signature = (item, count=1))]
+    fn py_drop_item(&mut self, item: Item, count: u32) -> Result<()> {
+        self.drop_item(item, count)
+    }
+
+    /// list of Door
+    #[pyo3(name = "doors_here")]
+    fn py_doors_here(&self) -> Vec<Door> {
+        self.doors_here()
+    }
+
+    /// False if the exit can't be reached any more with the keys left
+    #[pyo3(name = "solvable")]
+    fn py_solvable(&self) -> bool {
+        self.solvable()
+    }
+
+    /// Route, or None if no `target` can be reached
+    #[pyo3(name = "route_to")]
+    fn py_route_to(&self, target: Target) -> Option<Route> {
+        self.route_to(target)
+    }
+
+    /// `from` and `to` as ((row, col), (x, y)); raises IndexError if either is off the map
+    #[pyo3(name = "route")]
+    fn py_route(&self, from: (Room, (usize, usize)), to: (Room, (usize, usize))) -> Result<Option<Route>> {
+        self.route(from, to)
+    }
+
+    /// Shop, or None outside shop rooms
+    #[pyo3(name = "shop")]
+    fn py_shop(&self) -> Option<Shop> {
+        self.shop()
+    }
+
+    /// Returns False if the game isn't `Playing`; raises ValueError, changing nothing,
+    /// if the trade can't go through
+    #[pyo3(name = "buy", signature = (item, count=1))]
+    fn py_buy(&mut self, item: Item, count: u32) -> Result<bool> {
+        self.buy(item, count)
+    }
+
+    /// Returns False if the game isn't `Playing`; raises ValueError, changing nothing,
+    /// if the trade can't go through
+    #[pyo3(name = "sell", signature = (item, count=1))]
+    fn py_sell(&mut self, item: Item, count: u32) -> Result<bool> {
+        self.sell(item, count)
+    }
+
+    /// New stock for every Shop
+    #[pyo3(name = "restock")]
+    fn py_restock(&mut self) {
+        self.restock();
+    }
+
+    /// list[list[bool]] for the current room, [row][col]
+    #[pyo3(name = "visible_cells")]
+    fn py_visible_cells(&self) -> Vec<Vec<bool>> {
+        self.visible_cells()
+    }
+
+    /// list[list[bool]], [row][col]; raises IndexError for a room off the grid
+    #[pyo3(name = "explored_cells")]
+    fn py_explored_cells(&self, row: usize, col: usize) -> Result<Vec<Vec<bool>>> {
+        self.explored_cells(row, col)
+    }
+
+    /// list[list[float]], one per room
+    #[pyo3(name = "exploration")]
+    fn py_exploration(&self) -> Vec<Vec<f64>> {
+        self.exploration()
+    }
+
+    /// list of Entity; raises IndexError for a room off the grid
+    #[pyo3(name = "room_enemies")]
+    fn py_room_enemies(&self, row: usize, col: usize) -> Result<Vec<Entity>> {
+        self.room_enemies(row, col)
+    }
+
+    /// True while a living boss keeps `move_player` from leaving the room
+    #[pyo3(name = "is_sealed")]
+    fn py_is_sealed(&self) -> bool {
+        self.is_sealed()
+    }
+
+    /// Same as `state == GameState.Won`
+    #[pyo3(name = "is_won")]
+    fn py_is_won(&self) -> bool {
+        self.is_won()
+    }
+
+    #[pyo3(name = "is_lost")]
+    fn py_is_lost(&self) -> bool {
+        self.is_lost()
+    }
+
+    /// Moves, items and trades return False until `resume`; no effect once the game is over
+    #[pyo3(name = "pause")]
+    fn py_pause(&mut self) {
+        self.pause();
+    }
+
+    #[pyo3(name = "resume")]
+    fn py_resume(&mut self) {
+        self.resume();
+    }
+
+    /// Raises IndexError for a room off the grid; `maze` isn't checked, so run `validate` after
+    #[pyo3(name = "set_room")]
+    fn py_set_room(&mut self, row: usize, col: usize, maze: Maze) -> Result<()> {
+        self.set_room(row, col, maze)
+    }
+
+    /// A copy; edit rooms with `set_room`
+    #[pyo3(name = "current_maze")]
+    fn py_current_maze(&self) -> Maze {
+        self.current_maze()
+    }
+
+    /// Like `Maze.wall_array`, for the current room without copying it first
+    fn wall_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        buffer::u8_array(py, maze.wall_masks(), &[maze.height, maze.width])
+    }
+
+    /// (height, width) uint8 array: 0 nothing, 1 the player, 2 an enemy, 3 an unopened chest
+    fn occupancy_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        let maze = &self.mazes[self.current_room_row][self.current_room_col];
+        buffer::u8_array(py, self.occupancy(), &[maze.height, maze.width])
+    }
+}
+
+#[pymethods]
+impl Shop {
+    /// (row, col)
+    #[getter]
+    fn room(&self) -> Room {
+        self.room
+    }
+
+    /// Same as `Dungeon.floor()` in this room
+    #[getter]
+    fn floor(&self) -> usize {
+        self.floor
+    }
+
+    /// list of (Item, count)
+    #[getter]
+    fn stock(&self) -> Vec<(Item, u32)> {
+        self.stock.clone()
+    }
+
+    /// Gold for one; raises ValueError for keys
+    #[pyo3(name = "price")]
+    fn py_price(&self, item: Item) -> Result<u32> {
+        self.price(item)
+    }
+
+    /// Gold paid for one; raises ValueError for keys
+    #[pyo3(name = "sell_price")]
+    fn py_sell_price(&self, item: Item) -> Result<u32> {
+        self.sell_price(item)
+    }
+
+    #[pyo3(name = "count")]
+    fn py_count(&self, item: Item) -> u32 {
+        self.count(item)
+    }
+}
+
+#[pymethods]
+impl Door {
+    /// `room` as (row, col)
+    #[pyo3(name = "passable_from")]
+    fn py_passable_from(&self, room: Room) -> bool {
+        self.passable_from(room)
+    }
+
+    /// `a` and `b` as (row, col)
+    #[pyo3(name = "joins")]
+    fn py_joins(&self, a: Room, b: Room) -> bool {
+        self.joins(a, b)
+    }
+}
+
+#[pymethods]
+impl Route {
+    /// ((row, col), (x, y))
+    #[getter(goal)]
+    fn py_goal(&self) -> (Room, (usize, usize)) {
+        self.goal()
+    }
+
+    /// "up", "right", "down", "left" or None; pass it to `move_player`
+    #[pyo3(name = "first_move")]
+    fn py_first_move(&self) -> Option<&'static str> {
+        self.first_move()
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
+
+#[pymethods]
+impl Diagnostic {
+    fn __repr__(&self) -> String {
+        self.to_string()
+    }
+}
+
+#[pymethods]
+impl Respawn {
+    #[staticmethod]
+    #[pyo3(name = "never")]
+    fn py_never() -> Self {
+        Respawn::never()
+    }
+    #[staticmethod]
+    #[pyo3(name = "after_turns")]
+    fn py_after_turns(turns: u64) -> Self {
+        Respawn::after_turns(turns)
+    }
+    #[staticmethod]
+    #[pyo3(name = "always")]
+    fn py_always() -> Self {
+        Respawn::always()
+    }
+}
@@ -0,0 +1,201 @@
+// --- Entity and Inventory bindings ---
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use crate::entity::Entity;
+use crate::entity::inventory::{Inventory, Item, Slot};
+use crate::entity::kind::EnemyKind;
+use crate::error::Result;
+use crate::save;
+
+#[pymethods]
+impl Entity {
+    #[new]
+    #[pyo3(signature = (x, y, health, armor, attack, gold, is_player, keys=0))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(x: usize, y: usize, health: i32, armor: i32, attack: i32, gold: i32, is_player: bool, keys: u32) -> Self {
+        Entity::new(x, y, health, armor, attack, gold, is_player, keys)
+    }
+
+    /// An enemy of the given kind with its stat block for `floor`.
+    #[staticmethod]
+    #[pyo3(name = "enemy", signature = (kind, x, y, floor=0))]
+    fn py_enemy(kind: EnemyKind, x: usize, y: usize, floor: usize) -> Self {
+        Entity::enemy(kind, x, y, floor)
+    }
+
+    #[getter]
+    fn x(&self) -> usize {
+        self.x
+    }
+
+    #[setter]
+    fn set_x(&mut self, x: usize) {
+        self.x = x;
+    }
+
+    #[getter]
+    fn y(&self) -> usize {
+        self.y
+    }
+
+    #[setter]
+    fn set_y(&mut self, y: usize) {
+        self.y = y;
+    }
+
+    #[getter]
+    fn health(&self) -> i32 {
+        self.health
+    }
+
+    #[setter]
+    fn set_health(&mut self, health: i32) {
+        self.health = health;
+    }
+
+    #[getter]
+    fn armor(&self) -> i32 {
+        self.armor
+    }
+
+    #[setter]
+    fn set_armor(&mut self, armor: i32) {
+        self.armor = armor;
+    }
+
+    #[getter]
+    fn attack(&self) -> i32 {
+        self.attack
+    }
+
+    #[setter]
+    fn set_attack(&mut self, attack: i32) {
+        self.attack = attack;
+    }
+
+    #[getter]
+    fn gold(&self) -> i32 {
+        self.gold
+    }
+
+    #[setter]
+    fn set_gold(&mut self, gold: i32) {
+        self.gold = gold;
+    }
+
+    #[getter]
+    fn is_player(&self) -> bool {
+        self.is_player
+    }
+
+    #[setter]
+    fn set_is_player(&mut self, is_player: bool) {
+        self.is_player = is_player;
+    }
+
+    #[getter]
+    fn inventory(&self) -> Inventory {
+        self.inventory.clone()
+    }
+
+    /// Archetype of an enemy; None for the player
+    #[getter]
+    fn kind(&self) -> Option<EnemyKind> {
+        self.kind
+    }
+
+    #[getter(keys)]
+    fn py_keys(&self) -> u32 {
+        self.keys()
+    }
+
+    #[setter(keys)]
+    fn py_set_keys(&mut self, keys: u32) {
+        self.set_keys(keys);
+    }
+
+    /// Put a carried sword or shield into its slot. Whatever was there goes back in the bag.
+    #[pyo3(name = "equip")]
+    fn py_equip(&mut self, item: Item) -> Result<()> {
+        self.equip(item)
+    }
+
+    /// Move the item in `slot` back into the bag. Returns false if the slot was empty.
+    #[pyo3(name = "unequip")]
+    fn py_unequip(&mut self, slot: Slot) -> bool {
+        self.unequip(slot)
+    }
+
+    /// Drink a potion, or equip a sword/shield. Keys are spent by doors, not used directly.
+    #[pyo3(name = "use_item")]
+    fn py_use_item(&mut self, item: Item) -> Result<()> {
+        self.use_item(item)
+    }
+
+    /// Throw away carried items. Equipped items have to be unequipped first.
+    #[pyo3(name = "drop", signature = (item, count=1))]
+    fn py_drop(&mut self, item: Item, count: u32) -> Result<()> {
+        self.drop_item(item, count)
+    }
+
+    #[pyo3(name = "move_to")]
+    fn py_move_to(&mut self, x: usize, y: usize) {
+        self.move_to(x, y);
+    }
+
+    #[pyo3(name = "set_position")]
+    fn py_set_position(&mut self, x: usize, y: usize) {
+        self.set_position(x, y);
+    }
+
+    #[pyo3(name = "take_damage")]
+    fn py_take_damage(&mut self, amount: i32) {
+        self.take_damage(amount);
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &save::encode(self)?))
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = save::decode(state)?;
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> (usize, usize, i32, i32, i32, i32, bool) {
+        (0, 0, 0, 0, 0, 0, false)
+    }
+}
+
+#[pymethods]
+impl Inventory {
+    #[getter]
+    fn weapon(&self) -> Option<Item> {
+        self.weapon
+    }
+
+    #[getter]
+    fn shield(&self) -> Option<Item> {
+        self.shield
+    }
+
+    #[pyo3(name = "count")]
+    fn py_count(&self, item: Item) -> u32 {
+        self.count(item)
+    }
+
+    /// Carried stacks as (item, count), in the order they were first picked up.
+    #[pyo3(name = "items")]
+    fn py_items(&self) -> Vec<(Item, u32)> {
+        self.items()
+    }
+
+    #[pyo3(name = "equipped")]
+    fn py_equipped(&self, slot: Slot) -> Option<Item> {
+        self.equipped(slot)
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
@@ -0,0 +1,181 @@
+// --- DungeonEnv, VecDungeonEnv and Rewards bindings ---
+//
+// Observations, rewards and done flags come back as numpy arrays over the Rust buffers
+// they were built in, not copies of them.
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use super::buffer;
+use crate::dungeon::Dungeon;
+use crate::entity::Entity;
+use crate::env::{self, DungeonEnv, Info, Rewards, Step, VecDungeonEnv};
+use crate::error::Result;
+
+fn info_dict<'py>(py: Python<'py>, info: &Info, shape: &[usize]) -> PyResult<Bound<'py, PyDict>> {
+    let dict = PyDict::new_bound(py);
+    dict.set_item("moved", info.moved)?;
+    dict.set_item("gold", info.gold)?;
+    dict.set_item("kills", info.kills)?;
+    dict.set_item("keys", info.keys)?;
+    dict.set_item("health", info.health)?;
+    dict.set_item("room", info.room)?;
+    dict.set_item("won", info.won)?;
+    dict.set_item("lost", info.lost)?;
+    dict.set_item("truncated", info.truncated)?;
+    dict.set_item("steps", info.steps)?;
+    if let Some(obs) = &info.final_observation {
+        dict.set_item("final_observation", buffer::u8_array(py, obs.clone(), shape)?)?;
+    }
+    Ok(dict)
+}
+
+#[pymethods]
+impl Rewards {
+    #[new]
+    #[pyo3(signature = (gold=0.01, kill=0.1, key=1.0, exit_progress=0.5, win=10.0, death=-5.0, step=-0.001))]
+    fn py_new(gold: f32, kill: f32, key: f32, exit_progress: f32, win: f32, death: f32, step: f32) -> Self {
+        Rewards::new(gold, kill, key, exit_progress, win, death, step)
+    }
+}
+
+#[pymethods]
+impl DungeonEnv {
+    #[new]
+    #[pyo3(signature = (rows=5, cols=5, room_width=11, room_height=11, rewards=None, max_steps=1000, player=None))]
+    fn py_new(
+        rows: usize,
+        cols: usize,
+        room_width: usize,
+        room_height: usize,
+        rewards: Option<Rewards>,
+        max_steps: u64,
+        player: Option<Entity>,
+    ) -> Result<Self> {
+        DungeonEnv::new(rows, cols, room_width, room_height, rewards, max_steps, player)
+    }
+
+    #[getter]
+    fn rewards(&self) -> Rewards {
+        self.rewards.clone()
+    }
+
+    #[setter]
+    fn set_rewards(&mut self, rewards: Rewards) {
+        self.rewards = rewards;
+    }
+
+    #[getter]
+    fn max_steps(&self) -> u64 {
+        self.max_steps
+    }
+
+    #[setter]
+    fn set_max_steps(&mut self, max_steps: u64) {
+        self.max_steps = max_steps;
+    }
+
+    /// (channels, height, width) of every observation
+    #[getter(observation_shape)]
+    fn py_observation_shape(&self) -> (usize, usize, usize) {
+        self.observation_shape()
+    }
+
+    /// Number of actions: 0-3 move up, right, down, left; 4 drinks a potion
+    #[getter]
+    fn action_count(&self) -> usize {
+        env::ACTIONS
+    }
+
+    /// Copy of the dungeon being played, e.g. for `to_png`. None before the first reset.
+    #[getter(dungeon)]
+    fn py_dungeon(&self) -> Option<Dungeon> {
+        self.dungeon().cloned()
+    }
+
+    #[pyo3(name = "reset", signature = (seed=None))]
+    fn py_reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        let obs = self.reset(seed)?;
+        let (c, h, w) = self.observation_shape();
+        buffer::u8_array(py, obs, &[c, h, w])
+    }
+
+    /// Play one action and return (obs, reward, done, info).
+    #[pyo3(name = "step")]
+    fn py_step<'py>(&mut self, py: Python<'py>, action: usize) -> PyResult<(Bound<'py, PyAny>, f32, bool, Bound<'py, PyDict>)> {
+        let Step { obs, reward, done, info } = self.step(action)?;
+        let (c, h, w) = self.observation_shape();
+        Ok((buffer::u8_array(py, obs, &[c, h, w])?, reward, done, info_dict(py, &info, &[c, h, w])?))
+    }
+}
+
+#[pymethods]
+impl VecDungeonEnv {
+    /// `count` copies of an environment built from the same arguments as `DungeonEnv`.
+    #[new]
+    #[pyo3(signature = (count, rows=5, cols=5, room_width=11, room_height=11, rewards=None, max_steps=1000, player=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        count: usize,
+        rows: usize,
+        cols: usize,
+        room_width: usize,
+        room_height: usize,
+        rewards: Option<Rewards>,
+        max_steps: u64,
+        player: Option<Entity>,
+    ) -> Result<Self> {
+        VecDungeonEnv::new(count, DungeonEnv::new(rows, cols, room_width, room_height, rewards, max_steps, player)?)
+    }
+
+    /// (count, channels, height, width) of the batched observations
+    #[getter]
+    fn observation_shape(&self) -> (usize, usize, usize, usize) {
+        let (c, h, w) = self.envs()[0].observation_shape();
+        (self.len(), c, h, w)
+    }
+
+    #[getter]
+    fn action_count(&self) -> usize {
+        env::ACTIONS
+    }
+
+    /// Copies of the dungeons being played, one per environment
+    #[getter]
+    fn dungeons(&self) -> Vec<Option<Dungeon>> {
+        self.envs().iter().map(|env| env.dungeon().cloned()).collect()
+    }
+
+    /// Reset every environment; environment `i` plays seed `seed + i`.
+    #[pyo3(name = "reset", signature = (seed=None))]
+    fn py_reset<'py>(&mut self, py: Python<'py>, seed: Option<u64>) -> PyResult<Bound<'py, PyAny>> {
+        let obs = py.allow_threads(|| self.reset(seed))?;
+        let (n, c, h, w) = self.observation_shape();
+        buffer::u8_array(py, obs, &[n, c, h, w])
+    }
+
+    /// Step every environment in parallel and return (obs, rewards, dones, infos).
+    /// Finished environments start over at once; see `final_observation` in their info.
+    #[pyo3(name = "step")]
+    #[allow(clippy::type_complexity)]
+    fn py_step<'py>(
+        &mut self,
+        py: Python<'py>,
+        actions: Vec<usize>,
+    ) -> PyResult<(Bound<'py, PyAny>, Bound<'py, PyAny>, Bound<'py, PyAny>, Vec<Bound<'py, PyDict>>)> {
+        let steps = py.allow_threads(|| self.step(&actions))?;
+        let (n, c, h, w) = self.observation_shape();
+        let obs = steps.iter().flat_map(|s| s.obs.iter().copied()).collect();
+        let rewards: Vec<f32> = steps.iter().map(|s| s.reward).collect();
+        let dones: Vec<bool> = steps.iter().map(|s| s.done).collect();
+        let infos = steps.iter().map(|s| info_dict(py, &s.info, &[c, h, w])).collect::<PyResult<_>>()?;
+        Ok((
+            buffer::u8_array(py, obs, &[n, c, h, w])?,
+            buffer::f32_array(py, &rewards, &[n])?,
+            buffer::bool_array(py, &dones, &[n])?,
+            infos,
+        ))
+    }
+
+    fn __len__(&self) -> usize {
+        self.len()
+    }
+}
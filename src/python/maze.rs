@@ -0,0 +1,231 @@
+// --- Maze, Chest and ChestContent bindings ---
+use pyo3::prelude::*;
+use pyo3::types::PyBytes;
+use super::buffer;
+use crate::entity::Entity;
+use crate::error::Result;
+use crate::maze::Maze;
+use crate::maze::chest::{Chest, ChestContent};
+use crate::maze::generator::Algorithm;
+use crate::render::{self, Occupants, RenderOptions};
+use crate::save;
+
+/// Player and enemy positions as read back by `parse_ascii`, each as (x, y)
+type Parsed = (Maze, Option<(usize, usize)>, Vec<(usize, usize)>);
+
+#[pymethods]
+impl Maze {
+    #[new]
+    fn py_new(width: usize, height: usize) -> Self {
+        Maze::new(width, height)
+    }
+
+    #[getter]
+    fn chests(&self) -> Vec<Chest> {
+        self.chests.clone()
+    }
+
+    #[getter]
+    fn algorithm(&self) -> Algorithm {
+        self.algorithm
+    }
+
+    #[pyo3(name = "greet")]
+    fn py_greet(&self) {
+        self.greet();
+    }
+
+    #[pyo3(name = "get_cell_walls")]
+    fn py_get_cell_walls(&self, row: usize, col: usize) -> Result<(bool, bool, bool, bool)> {
+        self.get_cell_walls(row, col)
+    }
+
+    #[pyo3(name = "set_cell_visited")]
+    fn py_set_cell_visited(&mut self, row: usize, col: usize) -> Result<()> {
+        self.set_cell_visited(row, col)
+    }
+
+    /// Cells in line of sight of (x, y), indexed [row][col]
+    #[pyo3(name = "visible_cells")]
+    fn py_visible_cells(&self, x: usize, y: usize) -> Result<Vec<Vec<bool>>> {
+        self.visible_cells(x, y)
+    }
+
+    /// Shortest walk between two (x, y) cells, both ends included; None if walls cut them off
+    #[pyo3(name = "shortest_path")]
+    fn py_shortest_path(&self, from: (usize, usize), to: (usize, usize)) -> Result<Option<Vec<(usize, usize)>>> {
+        self.shortest_path(from, to)
+    }
+
+    /// Steps from (x, y) to every cell, indexed [row][col]; None where it can't reach
+    #[pyo3(name = "distance_map")]
+    fn py_distance_map(&self, from: (usize, usize)) -> Result<Vec<Vec<Option<u32>>>> {
+        self.distance_map(from)
+    }
+
+    /// Cells that have been seen (or marked visited), indexed [row][col]
+    #[pyo3(name = "explored_cells")]
+    fn py_explored_cells(&self) -> Vec<Vec<bool>> {
+        self.explored_cells()
+    }
+
+    /// Share of the room's cells that have been seen, from 0.0 to 1.0
+    #[pyo3(name = "explored_fraction")]
+    fn py_explored_fraction(&self) -> f64 {
+        self.explored_fraction()
+    }
+
+    /// Wall bitmasks as a (height, width) uint8 numpy array (1 = top, 2 = right, 4 = bottom, 8 = left)
+    fn wall_array<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyAny>> {
+        buffer::u8_array(py, self.wall_masks(), &[self.height, self.width])
+    }
+
+    #[pyo3(name = "remove_wall")]
+    fn py_remove_wall(&mut self, row: usize, col: usize, dir: usize) -> Result<()> {
+        self.remove_wall(row, col, dir)
+    }
+
+    /// Carve the maze. The same `seed` and `algorithm` always yield the same walls and chests.
+    ///
+    /// `braid` is the fraction of dead ends (0.0 to 1.0) turned into loops after carving.
+    #[pyo3(name = "generate_maze", signature = (exits=None, seed=None, algorithm=None, braid=0.0))]
+    fn py_generate_maze(&mut self, exits: Option<Vec<String>>, seed: Option<u64>, algorithm: Option<Algorithm>, braid: f64) -> Result<()> {
+        self.generate_maze(exits, seed, algorithm, braid)
+    }
+
+    #[pyo3(name = "display")]
+    fn py_display(&self) {
+        self.display();
+    }
+
+    /// Draw the room in the `display` format, optionally marking chests, the player and enemies.
+    #[pyo3(name = "to_ascii", signature = (chests=false, player=None, enemies=None))]
+    fn py_to_ascii(&self, chests: bool, player: Option<Entity>, enemies: Option<Vec<Entity>>) -> String {
+        self.to_ascii(chests, player, enemies)
+    }
+
+    /// Parse a drawing made by `to_ascii` (or by hand). Chest glyphs become chests;
+    /// player and enemy markers are ignored, see `parse_ascii`.
+    #[staticmethod]
+    #[pyo3(name = "from_ascii")]
+    fn py_from_ascii(text: &str) -> Result<Maze> {
+        Maze::from_ascii(text)
+    }
+
+    /// Like `from_ascii`, but also returns the player's and enemies' (x, y) positions.
+    #[staticmethod]
+    #[pyo3(name = "parse_ascii")]
+    fn py_parse_ascii(text: &str) -> Result<Parsed> {
+        Maze::parse_ascii(text)
+    }
+
+    /// Export walls and chests as JSON (schema documented in `maze/json.rs`).
+    #[pyo3(name = "to_json", signature = (pretty=false))]
+    fn py_to_json(&self, pretty: bool) -> Result<String> {
+        self.to_json(pretty)
+    }
+
+    /// Import a room written by `to_json` or an external editor.
+    /// Rejects walls that one cell sees as open and its neighbour as closed.
+    #[staticmethod]
+    #[pyo3(name = "from_json")]
+    fn py_from_json(data: &str) -> Result<Maze> {
+        Maze::from_json(data)
+    }
+
+    /// Draw the room as an SVG document.
+    #[pyo3(name = "to_svg", signature = (options=None, player=None, enemies=None))]
+    fn py_to_svg(&self, options: Option<RenderOptions>, player: Option<Entity>, enemies: Option<Vec<Entity>>) -> String {
+        self.to_svg(options, player, enemies)
+    }
+
+    /// Draw the room as PNG bytes.
+    #[pyo3(signature = (options=None, player=None, enemies=None))]
+    fn to_png<'py>(&self, py: Python<'py>, options: Option<RenderOptions>, player: Option<Entity>, enemies: Option<Vec<Entity>>) -> PyResult<Bound<'py, PyBytes>> {
+        let occupants = Occupants { player: player.as_ref(), enemies: enemies.as_deref().unwrap_or(&[]) };
+        Ok(PyBytes::new_bound(py, &render::maze_png(self, &occupants, &options.unwrap_or_default())?))
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &save::encode(self)?))
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = save::decode(state)?;
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> (usize, usize) {
+        (0, 0)
+    }
+}
+
+#[pymethods]
+impl ChestContent {
+    #[staticmethod]
+    #[pyo3(name = "gold")]
+    fn py_gold(amount: u32) -> Self {
+        ChestContent::gold(amount)
+    }
+    #[staticmethod]
+    #[pyo3(name = "sword")]
+    fn py_sword() -> Self {
+        ChestContent::sword()
+    }
+    #[staticmethod]
+    #[pyo3(name = "shield")]
+    fn py_shield() -> Self {
+        ChestContent::shield()
+    }
+    #[staticmethod]
+    #[pyo3(name = "potion")]
+    fn py_potion() -> Self {
+        ChestContent::potion()
+    }
+    #[staticmethod]
+    #[pyo3(name = "key")]
+    fn py_key() -> Self {
+        ChestContent::key()
+    }
+    #[staticmethod]
+    #[pyo3(name = "greatsword")]
+    fn py_greatsword() -> Self {
+        ChestContent::greatsword()
+    }
+    #[staticmethod]
+    #[pyo3(name = "aegis")]
+    fn py_aegis() -> Self {
+        ChestContent::aegis()
+    }
+}
+
+#[pymethods]
+impl Chest {
+    #[new]
+    fn py_new(row: usize, col: usize, contents: Option<ChestContent>) -> Self {
+        Chest::new(row, col, contents)
+    }
+
+    #[pyo3(name = "open")]
+    fn py_open(&mut self) -> Option<ChestContent> {
+        self.open()
+    }
+
+    #[pyo3(name = "is_empty")]
+    fn py_is_empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    fn __getstate__<'py>(&self, py: Python<'py>) -> PyResult<Bound<'py, PyBytes>> {
+        Ok(PyBytes::new_bound(py, &save::encode(self)?))
+    }
+
+    fn __setstate__(&mut self, state: &[u8]) -> PyResult<()> {
+        *self = save::decode(state)?;
+        Ok(())
+    }
+
+    fn __getnewargs__(&self) -> (usize, usize, Option<ChestContent>) {
+        (0, 0, None)
+    }
+}
@@ -0,0 +1,71 @@
+// --- Python bindings ---
+//
+// Only built with the `python` feature. The core types are marked `#[pyclass]` where
+// they are declared; the `#[pymethods]` here forward to their Rust methods, keeping the
+// Python names, defaults and exceptions. Python-only bits (pickling, PNG bytes) live
+// here too.
+mod buffer;
+mod dungeon;
+mod entity;
+mod env;
+mod maze;
+mod render;
+
+use pyo3::exceptions::{PyIndexError, PyValueError};
+use pyo3::prelude::*;
+use crate::error::Error;
+use crate::maze::Maze;
+use crate::maze::generator::Algorithm;
+use crate::maze::chest::{Chest, ChestContent};
+use crate::dungeon::Dungeon;
+use crate::dungeon::boss::BossPhase;
+use crate::dungeon::door::{Door, DoorKind};
+use crate::dungeon::room::Respawn;
+use crate::dungeon::route::{Route, Target};
+use crate::dungeon::shop::Shop;
+use crate::dungeon::state::GameState;
+use crate::dungeon::validate::{Diagnostic, Problem};
+use crate::entity::Entity;
+use crate::entity::inventory::{Inventory, Item, Slot};
+use crate::entity::kind::EnemyKind;
+use crate::env::{DungeonEnv, Rewards, VecDungeonEnv};
+use crate::render::RenderOptions;
+
+impl From<Error> for PyErr {
+    fn from(err: Error) -> PyErr {
+        match err {
+            Error::Invalid(message) => PyValueError::new_err(message),
+            Error::OutOfBounds(message) => PyIndexError::new_err(message),
+            Error::Io(err) => err.into(),
+        }
+    }
+}
+
+#[pymodule]
+fn dungeon_core(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add_class::<Maze>()?;
+    m.add_class::<Algorithm>()?;
+    m.add_class::<Chest>()?;
+    m.add_class::<ChestContent>()?;
+    m.add_class::<Dungeon>()?;
+    m.add_class::<GameState>()?;
+    m.add_class::<Diagnostic>()?;
+    m.add_class::<Problem>()?;
+    m.add_class::<BossPhase>()?;
+    m.add_class::<Door>()?;
+    m.add_class::<DoorKind>()?;
+    m.add_class::<Respawn>()?;
+    m.add_class::<Route>()?;
+    m.add_class::<Target>()?;
+    m.add_class::<Shop>()?;
+    m.add_class::<Entity>()?;
+    m.add_class::<Inventory>()?;
+    m.add_class::<Item>()?;
+    m.add_class::<Slot>()?;
+    m.add_class::<EnemyKind>()?;
+    m.add_class::<RenderOptions>()?;
+    m.add_class::<DungeonEnv>()?;
+    m.add_class::<VecDungeonEnv>()?;
+    m.add_class::<Rewards>()?;
+    Ok(())
+}
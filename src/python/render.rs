@@ -0,0 +1,22 @@
+// --- RenderOptions bindings ---
+use pyo3::prelude::*;
+use crate::render::RenderOptions;
+
+#[pymethods]
+impl RenderOptions {
+    #[new]
+    #[pyo3(signature = (cell_size=24, wall_thickness=2.0, chests=true, enemies=true, player=true, solution=false, visited_rooms=false, path=None))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        cell_size: u32,
+        wall_thickness: f32,
+        chests: bool,
+        enemies: bool,
+        player: bool,
+        solution: bool,
+        visited_rooms: bool,
+        path: Option<Vec<(usize, usize)>>,
+    ) -> Self {
+        RenderOptions::new(cell_size, wall_thickness, chests, enemies, player, solution, visited_rooms, path)
+    }
+}
@@ -0,0 +1,273 @@
+// --- Headless rendering ---
+//
+// Draws rooms and whole dungeons without a window, for CI artifacts, bug reports
+// and seed galleries. The scene is drawn once against the `Canvas` trait, then
+// written out either as SVG text or rasterized to PNG with tiny-skia.
+mod raster;
+mod svg;
+
+#[cfg(feature = "python")]
+use pyo3::prelude::*;
+use crate::dungeon::Dungeon;
+use crate::entity::Entity;
+use crate::error::Result;
+use crate::maze::Maze;
+use crate::pathfinding::DistanceMap;
+
+pub use raster::Raster;
+pub use svg::Svg;
+
+pub type Rgba = (u8, u8, u8, u8);
+
+const BACKGROUND: Rgba = (0, 0, 0, 255);
+const WALL: Rgba = (255, 255, 255, 255);
+const VISITED: Rgba = (45, 45, 80, 255);
+const CURRENT_ROOM: Rgba = (0, 255, 0, 255);
+const CHEST: Rgba = (218, 165, 32, 255);
+const OPEN_CHEST: Rgba = (110, 85, 30, 255);
+const ENEMY: Rgba = (220, 40, 40, 255);
+const PLAYER: Rgba = (0, 255, 0, 255);
+const PATH: Rgba = (60, 140, 255, 200);
+
+/// Drawing primitives shared by the SVG and PNG backends.
+pub trait Canvas {
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: Rgba);
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32, color: Rgba);
+    fn circle(&mut self, cx: f32, cy: f32, r: f32, color: Rgba);
+    fn polyline(&mut self, points: &[(f32, f32)], width: f32, color: Rgba);
+}
+
+/// What to draw and at which scale.
+#[cfg_attr(feature = "python", pyclass(module = "dungeon_core", get_all, set_all))]
+#[derive(Clone, Debug)]
+pub struct RenderOptions {
+    /// Side of one maze cell in pixels
+    pub cell_size: u32,
+    pub wall_thickness: f32,
+    pub chests: bool,
+    pub enemies: bool,
+    pub player: bool,
+    /// Shortest route linking every exit of a room
+    pub solution: bool,
+    /// Shade rooms the player has been in (dungeon renders only)
+    pub visited_rooms: bool,
+    /// Extra cells to highlight as a path, as (x, y). Dungeon renders draw it in the
+    /// player's room only.
+    pub path: Vec<(usize, usize)>,
+}
+
+impl RenderOptions {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        cell_size: u32,
+        wall_thickness: f32,
+        chests: bool,
+        enemies: bool,
+        player: bool,
+        solution: bool,
+        visited_rooms: bool,
+        path: Option<Vec<(usize, usize)>>,
+    ) -> Self {
+        RenderOptions { cell_size, wall_thickness, chests, enemies, player, solution, visited_rooms, path: path.unwrap_or_default() }
+    }
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions::new(24, 2.0, true, true, true, false, false, None)
+    }
+}
+
+/// Entities standing in the room being drawn.
+pub struct Occupants<'a> {
+    pub player: Option<&'a Entity>,
+    pub enemies: &'a [Entity],
+}
+
+impl Occupants<'_> {
+    pub const NONE: Occupants<'static> = Occupants { player: None, enemies: &[] };
+}
+
+/// Pixel size of a single room.
+pub fn maze_size(maze: &Maze, opts: &RenderOptions) -> (u32, u32) {
+    let pad = room_padding(opts);
+    (maze.width as u32 * opts.cell_size + 2 * pad, maze.height as u32 * opts.cell_size + 2 * pad)
+}
+
+/// Pixel size of the whole dungeon grid.
+pub fn dungeon_size(dungeon: &Dungeon, opts: &RenderOptions) -> (u32, u32) {
+    let (room_w, room_h) = dungeon.mazes.first().and_then(|r| r.first()).map_or((0, 0), |m| maze_size(m, opts));
+    let rows = dungeon.mazes.len() as u32;
+    let cols = dungeon.mazes.first().map_or(0, |r| r.len()) as u32;
+    (cols * room_w, rows * room_h)
+}
+
+fn room_padding(opts: &RenderOptions) -> u32 {
+    opts.cell_size / 2
+}
+
+pub fn draw_maze(canvas: &mut dyn Canvas, maze: &Maze, occupants: &Occupants, opts: &RenderOptions) {
+    let (w, h) = maze_size(maze, opts);
+    canvas.rect(0.0, 0.0, w as f32, h as f32, BACKGROUND);
+    draw_room(canvas, maze, (0.0, 0.0), occupants, &opts.path, opts);
+}
+
+pub fn draw_dungeon(canvas: &mut dyn Canvas, dungeon: &Dungeon, opts: &RenderOptions) {
+    let (w, h) = dungeon_size(dungeon, opts);
+    canvas.rect(0.0, 0.0, w as f32, h as f32, BACKGROUND);
+
+    for (r, row) in dungeon.mazes.iter().enumerate() {
+        for (c, maze) in row.iter().enumerate() {
+            let (room_w, room_h) = maze_size(maze, opts);
+            let origin = (c as f32 * room_w as f32, r as f32 * room_h as f32);
+            let pad = room_padding(opts) as f32;
+            let is_current = r == dungeon.current_room_row && c == dungeon.current_room_col;
+
+            if opts.visited_rooms && dungeon.visited_rooms[r][c] {
+                canvas.rect(origin.0 + pad, origin.1 + pad, room_w as f32 - 2.0 * pad, room_h as f32 - 2.0 * pad, VISITED);
+            }
+            if is_current {
+                let (x1, y1) = (origin.0 + pad / 2.0, origin.1 + pad / 2.0);
+                let (x2, y2) = (origin.0 + room_w as f32 - pad / 2.0, origin.1 + room_h as f32 - pad / 2.0);
+                canvas.polyline(&[(x1, y1), (x2, y1), (x2, y2), (x1, y2), (x1, y1)], 1.0, CURRENT_ROOM);
+            }
+
+            let occupants = if is_current {
+                Occupants { player: Some(&dungeon.player), enemies: &dungeon.enemies }
+            } else {
+                Occupants::NONE
+            };
+            // Path cells carry no room, so they belong to the room the player is in
+            let path = if is_current { opts.path.as_slice() } else { &[] };
+            draw_room(canvas, maze, origin, &occupants, path, opts);
+        }
+    }
+}
+
+fn draw_room(canvas: &mut dyn Canvas, maze: &Maze, origin: (f32, f32), occupants: &Occupants, path: &[(usize, usize)], opts: &RenderOptions) {
+    let size = opts.cell_size as f32;
+    let pad = room_padding(opts) as f32;
+    let cell_origin = |x: usize, y: usize| (origin.0 + pad + x as f32 * size, origin.1 + pad + y as f32 * size);
+    let cell_center = |x: usize, y: usize| {
+        let (cx, cy) = cell_origin(x, y);
+        (cx + size / 2.0, cy + size / 2.0)
+    };
+
+    if opts.solution {
+        for path in solution_paths(maze) {
+            let points: Vec<_> = path.iter().map(|&(x, y)| cell_center(x, y)).collect();
+            canvas.polyline(&points, size / 4.0, PATH);
+        }
+    }
+    if !path.is_empty() {
+        let points: Vec<_> = path.iter().map(|&(x, y)| cell_center(x, y)).collect();
+        canvas.polyline(&points, size / 4.0, PATH);
+    }
+
+    for row in 0..maze.height {
+        for col in 0..maze.width {
+            let (x, y) = cell_origin(col, row);
+            let walls = maze.get_cell_walls(row, col).expect("cell is inside the maze");
+            if walls.0 {
+                canvas.line(x, y, x + size, y, opts.wall_thickness, WALL);
+            }
+            if walls.1 {
+                canvas.line(x + size, y, x + size, y + size, opts.wall_thickness, WALL);
+            }
+            if walls.2 {
+                canvas.line(x, y + size, x + size, y + size, opts.wall_thickness, WALL);
+            }
+            if walls.3 {
+                canvas.line(x, y, x, y + size, opts.wall_thickness, WALL);
+            }
+        }
+    }
+
+    if opts.chests {
+        for chest in &maze.chests {
+            let (x, y) = cell_origin(chest.col, chest.row);
+            let inset = size * 0.2;
+            let color = if chest.is_open { OPEN_CHEST } else { CHEST };
+            canvas.rect(x + inset, y + inset, size - 2.0 * inset, size - 2.0 * inset, color);
+        }
+    }
+    if opts.enemies {
+        for enemy in occupants.enemies {
+            let (cx, cy) = cell_center(enemy.x, enemy.y);
+            canvas.circle(cx, cy, size * 0.3, ENEMY);
+        }
+    }
+    if opts.player && let Some(player) = occupants.player {
+        let (cx, cy) = cell_center(player.x, player.y);
+        canvas.circle(cx, cy, size * 0.35, PLAYER);
+    }
+}
+
+/// Cells (x, y) just inside each open outer wall.
+fn exit_cells(maze: &Maze) -> Vec<(usize, usize)> {
+    let mut exits = Vec::new();
+    for row in 0..maze.height {
+        for col in 0..maze.width {
+            let on_edge = [row == 0, col + 1 == maze.width, row + 1 == maze.height, col == 0];
+            if (0..4).any(|dir| on_edge[dir] && maze.can_move(row, col, dir)) {
+                exits.push((col, row));
+            }
+        }
+    }
+    exits
+}
+
+/// Shortest path from the first exit to every other exit.
+fn solution_paths(maze: &Maze) -> Vec<Vec<(usize, usize)>> {
+    let exits = exit_cells(maze);
+    let Some(&start) = exits.first() else {
+        return Vec::new();
+    };
+
+    let distances = DistanceMap::new(maze, &[start]);
+    exits[1..].iter().filter_map(|&goal| distances.path(maze, goal)).collect()
+}
+
+pub fn maze_svg(maze: &Maze, occupants: &Occupants, opts: &RenderOptions) -> String {
+    let (w, h) = maze_size(maze, opts);
+    let mut svg = Svg::new(w, h);
+    draw_maze(&mut svg, maze, occupants, opts);
+    svg.finish()
+}
+
+pub fn maze_png(maze: &Maze, occupants: &Occupants, opts: &RenderOptions) -> Result<Vec<u8>> {
+    let (w, h) = maze_size(maze, opts);
+    let mut raster = Raster::new(w, h)?;
+    draw_maze(&mut raster, maze, occupants, opts);
+    raster.encode_png()
+}
+
+pub fn dungeon_svg(dungeon: &Dungeon, opts: &RenderOptions) -> String {
+    let (w, h) = dungeon_size(dungeon, opts);
+    let mut svg = Svg::new(w, h);
+    draw_dungeon(&mut svg, dungeon, opts);
+    svg.finish()
+}
+
+pub fn dungeon_png(dungeon: &Dungeon, opts: &RenderOptions) -> Result<Vec<u8>> {
+    let (w, h) = dungeon_size(dungeon, opts);
+    let mut raster = Raster::new(w, h)?;
+    draw_dungeon(&mut raster, dungeon, opts);
+    raster.encode_png()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dungeon::test_dungeon;
+
+    #[test]
+    fn dungeon_path_is_drawn_once() {
+        let dungeon = test_dungeon(4);
+        let lines = |path: Option<Vec<(usize, usize)>>| {
+            let opts = RenderOptions::new(24, 2.0, true, true, true, false, false, path);
+            dungeon.to_svg(Some(opts)).matches("<polyline").count()
+        };
+        assert_eq!(lines(Some(vec![(3, 3), (3, 2), (3, 1)])), lines(None) + 1);
+    }
+}
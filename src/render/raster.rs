@@ -0,0 +1,68 @@
+use tiny_skia::{FillRule, LineCap, LineJoin, Paint, PathBuilder, Pixmap, Rect, Stroke, Transform};
+use super::{Canvas, Rgba};
+use crate::error::{Error, Result};
+
+/// Rasterizes onto an in-memory pixmap with tiny-skia.
+pub struct Raster {
+    pixmap: Pixmap,
+}
+
+impl Raster {
+    pub fn new(width: u32, height: u32) -> Result<Self> {
+        let pixmap = Pixmap::new(width, height)
+            .ok_or_else(|| Error::invalid(format!("Cannot render a {}x{} image", width, height)))?;
+        Ok(Raster { pixmap })
+    }
+
+    pub fn encode_png(&self) -> Result<Vec<u8>> {
+        self.pixmap
+            .encode_png()
+            .map_err(|e| Error::invalid(format!("Failed to encode PNG: {}", e)))
+    }
+
+    fn stroke(&mut self, points: &[(f32, f32)], width: f32, c: Rgba, cap: LineCap) {
+        let mut pb = PathBuilder::new();
+        let Some((&(x, y), rest)) = points.split_first() else {
+            return;
+        };
+        pb.move_to(x, y);
+        for &(x, y) in rest {
+            pb.line_to(x, y);
+        }
+        if let Some(path) = pb.finish() {
+            let stroke = Stroke { width, line_cap: cap, line_join: LineJoin::Round, ..Stroke::default() };
+            self.pixmap.stroke_path(&path, &paint(c), &stroke, Transform::identity(), None);
+        }
+    }
+}
+
+fn paint(c: Rgba) -> Paint<'static> {
+    let mut paint = Paint::default();
+    paint.set_color_rgba8(c.0, c.1, c.2, c.3);
+    paint.anti_alias = true;
+    paint
+}
+
+impl Canvas for Raster {
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, c: Rgba) {
+        if let Some(rect) = Rect::from_xywh(x, y, w, h) {
+            self.pixmap.fill_rect(rect, &paint(c), Transform::identity(), None);
+        }
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32, c: Rgba) {
+        self.stroke(&[(x1, y1), (x2, y2)], width, c, LineCap::Square);
+    }
+
+    fn circle(&mut self, cx: f32, cy: f32, r: f32, c: Rgba) {
+        if let Some(path) = PathBuilder::from_circle(cx, cy, r) {
+            self.pixmap.fill_path(&path, &paint(c), FillRule::Winding, Transform::identity(), None);
+        }
+    }
+
+    fn polyline(&mut self, points: &[(f32, f32)], width: f32, c: Rgba) {
+        if points.len() >= 2 {
+            self.stroke(points, width, c, LineCap::Round);
+        }
+    }
+}
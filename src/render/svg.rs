@@ -0,0 +1,64 @@
+use std::fmt::Write;
+use super::{Canvas, Rgba};
+
+/// Builds an SVG document as text.
+pub struct Svg {
+    body: String,
+    width: u32,
+    height: u32,
+}
+
+impl Svg {
+    pub fn new(width: u32, height: u32) -> Self {
+        Svg { body: String::new(), width, height }
+    }
+
+    pub fn finish(self) -> String {
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{w}\" height=\"{h}\" viewBox=\"0 0 {w} {h}\">\n{body}</svg>\n",
+            w = self.width,
+            h = self.height,
+            body = self.body
+        )
+    }
+}
+
+fn color(c: Rgba) -> String {
+    if c.3 == 255 {
+        format!("rgb({},{},{})", c.0, c.1, c.2)
+    } else {
+        format!("rgba({},{},{},{:.3})", c.0, c.1, c.2, c.3 as f32 / 255.0)
+    }
+}
+
+impl Canvas for Svg {
+    fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, c: Rgba) {
+        let _ = writeln!(self.body, "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>", x, y, w, h, color(c));
+    }
+
+    fn line(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, width: f32, c: Rgba) {
+        let _ = writeln!(
+            self.body,
+            "<line x1=\"{}\" y1=\"{}\" x2=\"{}\" y2=\"{}\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"square\"/>",
+            x1, y1, x2, y2, color(c), width
+        );
+    }
+
+    fn circle(&mut self, cx: f32, cy: f32, r: f32, c: Rgba) {
+        let _ = writeln!(self.body, "<circle cx=\"{}\" cy=\"{}\" r=\"{}\" fill=\"{}\"/>", cx, cy, r, color(c));
+    }
+
+    fn polyline(&mut self, points: &[(f32, f32)], width: f32, c: Rgba) {
+        if points.len() < 2 {
+            return;
+        }
+        let coords: Vec<String> = points.iter().map(|(x, y)| format!("{},{}", x, y)).collect();
+        let _ = writeln!(
+            self.body,
+            "<polyline points=\"{}\" fill=\"none\" stroke=\"{}\" stroke-width=\"{}\" stroke-linecap=\"round\" stroke-linejoin=\"round\"/>",
+            coords.join(" "),
+            color(c),
+            width
+        );
+    }
+}
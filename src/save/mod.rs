@@ -0,0 +1,93 @@
+// --- Save format ---
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use crate::error::{Error, Result};
+
+/// Every save starts with these bytes, followed by the format version (u16, little endian).
+pub const MAGIC: &[u8; 4] = b"DE2D";
+/// Bump this once per released change to a saved type's shape, and teach `migrate`
+/// to turn the previous release's layout into the new one.
+pub const FORMAT_VERSION: u16 = 1;
+
+const HEADER_LEN: usize = MAGIC.len() + 2;
+
+/// Serialize a value behind the versioned header.
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>> {
+    let mut bytes = Vec::with_capacity(HEADER_LEN);
+    bytes.extend_from_slice(MAGIC);
+    bytes.extend_from_slice(&FORMAT_VERSION.to_le_bytes());
+    bincode::serialize_into(&mut bytes, value)
+        .map_err(|e| Error::invalid(format!("Failed to encode save: {}", e)))?;
+    Ok(bytes)
+}
+
+/// Check the header, migrate older payloads and deserialize.
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T> {
+    if bytes.len() < HEADER_LEN || &bytes[..MAGIC.len()] != MAGIC {
+        return Err(Error::invalid("Not a DungeonEscape2D save"));
+    }
+    let version = u16::from_le_bytes([bytes[MAGIC.len()], bytes[MAGIC.len() + 1]]);
+    let payload = migrate(version, &bytes[HEADER_LEN..])?;
+    bincode::deserialize(&payload)
+        .map_err(|e| Error::invalid(format!("Corrupt save: {}", e)))
+}
+
+/// Upgrade a payload written by an older version to the current layout. Version 1 is
+/// the first released format, so there is nothing older to upgrade yet.
+fn migrate(version: u16, payload: &[u8]) -> Result<Vec<u8>> {
+    match version {
+        FORMAT_VERSION => Ok(payload.to_vec()),
+        v if v > FORMAT_VERSION => Err(Error::invalid(format!(
+            "Save format version {} is newer than supported version {}", v, FORMAT_VERSION
+        ))),
+        v => Err(Error::invalid(format!("No migration from save format version {}", v))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::maze::Maze;
+    use crate::maze::chest::Chest;
+    use crate::maze::generator::Algorithm;
+
+    /// Same shape as `Maze` on disk, with nothing checked
+    #[derive(Serialize)]
+    struct LooseMaze {
+        width: usize,
+        height: usize,
+        grid: (usize, usize, Vec<u64>),
+        chests: Vec<Chest>,
+        algorithm: Algorithm,
+    }
+
+    fn loose(width: usize, height: usize, grid: (usize, usize, Vec<u64>), chests: Vec<Chest>) -> Vec<u8> {
+        encode(&LooseMaze { width, height, grid, chests, algorithm: Algorithm::RecursiveBacktracker }).unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        let mut maze = Maze::new(4, 3);
+        maze.generate_maze(None, Some(2), None, 0.0).unwrap();
+        let back: Maze = decode(&encode(&maze).unwrap()).unwrap();
+        assert_eq!(back.wall_masks(), maze.wall_masks());
+    }
+
+    #[test]
+    fn checks_the_header() {
+        let mut bytes = encode(&Maze::new(2, 2)).unwrap();
+        assert!(decode::<Maze>(&bytes[..3]).is_err());
+        bytes[MAGIC.len()..HEADER_LEN].copy_from_slice(&(FORMAT_VERSION + 1).to_le_bytes());
+        assert!(decode::<Maze>(&bytes).is_err());
+    }
+
+    #[test]
+    fn corrupt_rooms_are_errors() {
+        // 2x2 needs 3 * 2 + 2 * 3 + 4 = 16 bits, one word
+        assert!(decode::<Maze>(&loose(2, 2, (2, 2, vec![0]), Vec::new())).is_ok());
+        assert!(decode::<Maze>(&loose(2, 2, (2, 2, Vec::new()), Vec::new())).is_err());
+        assert!(decode::<Maze>(&loose(2, 2, (usize::MAX, 2, vec![0]), Vec::new())).is_err());
+        assert!(decode::<Maze>(&loose(9, 9, (2, 2, vec![0]), Vec::new())).is_err());
+        assert!(decode::<Maze>(&loose(2, 2, (2, 2, vec![0]), vec![Chest::new(5, 0, None)])).is_err());
+    }
+}
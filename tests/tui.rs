@@ -0,0 +1,23 @@
+// --- dungeon-tui smoke test ---
+//
+// Plays a fixed sequence through `--moves`, which needs no terminal.
+use std::process::Command;
+
+fn play(args: &[&str]) -> String {
+    let output = Command::new(env!("CARGO_BIN_EXE_dungeon-tui")).args(args).output().expect("dungeon-tui runs");
+    assert!(output.status.success(), "{}", String::from_utf8_lossy(&output.stderr));
+    String::from_utf8(output.stdout).expect("frame is UTF-8")
+}
+
+#[test]
+fn moves_print_the_last_frame() {
+    let frame = play(&["--seed", "42", "--moves", "rrddllup"]);
+    assert!(frame.contains('@'), "{}", frame);
+    assert!(frame.contains("Keys 0/4"), "{}", frame);
+}
+
+#[test]
+fn tiny_rooms_do_not_hang() {
+    play(&["--seed", "3", "--room-size", "1", "--moves", "udlr"]);
+    play(&["--seed", "3", "--rows", "1", "--cols", "1", "--room-size", "2", "--moves", "udlrp"]);
+}